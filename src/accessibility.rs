@@ -0,0 +1,233 @@
+use serde_json::{Map, Value};
+
+use crate::compatibility::{escape_pointer, implicit_type};
+use crate::model::ValidationIssue;
+
+/// Longest text that still reads as a heading.
+const MAX_HEADING_CHARS: usize = 120;
+
+/// Container types whose `style` sets the background behind their content.
+const STYLED_CONTAINERS: &[&str] = &["Container", "Column", "ColumnSet", "TableCell"];
+
+/// Lints the card for accessibility problems. Issues are raised without a severity; the
+/// rule catalogue assigns it.
+pub(crate) fn validate_accessibility(card: &Value) -> Vec<ValidationIssue> {
+    let mut lint = Lint { issues: Vec::new() };
+    if let Some(map) = card.as_object() {
+        lint.visit(map, "AdaptiveCard", "", "", "default");
+    }
+    lint.issues
+}
+
+struct Lint {
+    issues: Vec<ValidationIssue>,
+}
+
+impl Lint {
+    fn report(&mut self, code: &str, path: &str, message: String) {
+        self.issues.push(ValidationIssue {
+            code: code.into(),
+            message,
+            path: if path.is_empty() {
+                "/".into()
+            } else {
+                path.into()
+            },
+            ..Default::default()
+        });
+    }
+
+    /// `key` is the property holding this node and `style` the container style behind it.
+    fn visit(&mut self, map: &Map<String, Value>, kind: &str, key: &str, path: &str, style: &str) {
+        let text = |name: &str| {
+            map.get(name)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        match kind {
+            "Image" if text("altText").is_none() => {
+                self.report(
+                    "a11y-image-alt-text",
+                    path,
+                    "Image should include altText for screen readers".into(),
+                );
+            }
+            "Media" => {
+                let captions = map
+                    .get("captionSources")
+                    .and_then(Value::as_array)
+                    .is_some_and(|sources| !sources.is_empty());
+                if !captions {
+                    self.report(
+                        "a11y-media-captions",
+                        path,
+                        "Media should include captionSources".into(),
+                    );
+                }
+            }
+            "TextBlock" | "TextRun" => self.check_text(map, kind, path, style),
+            _ if kind.starts_with("Input.") => {
+                if text("label").is_none() {
+                    self.report(
+                        "a11y-input-label",
+                        path,
+                        format!("{kind} should include a label"),
+                    );
+                }
+                let required = map.get("isRequired").and_then(Value::as_bool) == Some(true);
+                if required && text("errorMessage").is_none() {
+                    self.report(
+                        "a11y-required-error-message",
+                        path,
+                        format!("Required {kind} should include an errorMessage"),
+                    );
+                }
+            }
+            // Titles of `selectAction`/`inlineAction` are not shown, so only buttons are checked.
+            _ if kind.starts_with("Action.") && key == "actions" => {
+                let title = text("title");
+                let icon = text("iconUrl").is_some();
+                let readable = title.is_some_and(|title| title.chars().any(char::is_alphanumeric));
+                if icon && !readable {
+                    self.report(
+                        "a11y-icon-only-action",
+                        path,
+                        format!("{kind} shows only an icon; add a descriptive title"),
+                    );
+                } else if title.is_none() {
+                    self.report(
+                        "a11y-action-title",
+                        path,
+                        format!("{kind} should include a title"),
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        let style = if STYLED_CONTAINERS.contains(&kind) {
+            map.get("style").and_then(Value::as_str).unwrap_or(style)
+        } else {
+            style
+        };
+        for (child_key, child) in map {
+            if child_key == "data" {
+                continue;
+            }
+            let child_path = format!("{path}/{}", escape_pointer(child_key));
+            self.visit_child(child, kind, child_key, &child_path, style);
+        }
+    }
+
+    fn visit_child(&mut self, value: &Value, parent: &str, key: &str, path: &str, style: &str) {
+        match value {
+            Value::Object(map) => {
+                if let Some(kind) = map
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .or_else(|| implicit_type(parent, key))
+                {
+                    self.visit(map, kind, key, path, style);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.visit_child(item, parent, key, &format!("{path}/{index}"), style);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_text(&mut self, map: &Map<String, Value>, kind: &str, path: &str, style: &str) {
+        let field = |name: &str| {
+            map.get(name)
+                .and_then(Value::as_str)
+                .map(str::to_ascii_lowercase)
+                .unwrap_or_default()
+        };
+        let color = field("color");
+        let style = style.to_ascii_lowercase();
+        let light_on_light = color == "light" && matches!(style.as_str(), "default" | "emphasis");
+        let same_tone =
+            matches!(color.as_str(), "good" | "warning" | "attention" | "accent") && color == style;
+        if light_on_light || same_tone {
+            self.report(
+                "a11y-low-contrast",
+                path,
+                format!("{kind} color `{color}` has low contrast on a `{style}` container"),
+            );
+        }
+
+        if kind != "TextBlock" {
+            return;
+        }
+        let text = map.get("text").and_then(Value::as_str).unwrap_or_default();
+        if field("style") == "heading" {
+            if text.chars().count() > MAX_HEADING_CHARS {
+                self.report(
+                    "a11y-heading-style",
+                    path,
+                    format!(
+                        "Heading style on {} characters of text; use it for short headings only",
+                        text.chars().count()
+                    ),
+                );
+            }
+        } else if matches!(field("size").as_str(), "large" | "extralarge")
+            && field("weight") == "bolder"
+        {
+            self.report(
+                "a11y-heading-style",
+                path,
+                "Large bold TextBlock looks like a heading; set \"style\": \"heading\" so screen readers announce it"
+                    .into(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_accessibility_problems() {
+        let card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.6",
+            "body": [
+                { "type": "TextBlock", "text": "Order", "size": "Large", "weight": "Bolder" },
+                { "type": "ImageSet", "images": [ { "url": "a.png" }, { "url": "b.png", "altText": "B" } ] },
+                { "type": "Container", "style": "good", "items": [
+                    { "type": "TextBlock", "text": "Paid", "color": "good" }
+                ] },
+                { "type": "Input.Text", "id": "note", "isRequired": true, "label": "Note" },
+                { "type": "Media", "sources": [ { "url": "v.mp4" } ] }
+            ],
+            "actions": [
+                { "type": "Action.Submit", "title": "✓", "iconUrl": "check.png", "data": { "type": "Image" } },
+                { "type": "Action.OpenUrl", "url": "https://example.com" }
+            ]
+        });
+        let issues: Vec<(String, String)> = validate_accessibility(&card)
+            .into_iter()
+            .map(|issue| (issue.code, issue.path))
+            .collect();
+        let expected = [
+            ("a11y-icon-only-action", "/actions/0"),
+            ("a11y-action-title", "/actions/1"),
+            ("a11y-heading-style", "/body/0"),
+            ("a11y-image-alt-text", "/body/1/images/0"),
+            ("a11y-low-contrast", "/body/2/items/0"),
+            ("a11y-required-error-message", "/body/3"),
+            ("a11y-media-captions", "/body/4"),
+        ];
+        assert_eq!(
+            issues,
+            expected.map(|(code, path)| (code.to_string(), path.to_string()))
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use serde_json::{Map, Value};
+
+use crate::model::ValidationIssue;
+
+/// Adaptive Card minor version (all published versions are `1.x`).
+pub(crate) type Minor = u32;
+
+/// Element and action types introduced after 1.0.
+const TYPES: &[(&str, Minor)] = &[
+    ("Media", 1),
+    ("ActionSet", 2),
+    ("RichTextBlock", 2),
+    ("TextRun", 2),
+    ("Action.ToggleVisibility", 2),
+    ("Action.Execute", 4),
+    ("Table", 5),
+];
+
+/// Properties introduced after 1.0, by owner. `Element`, `Input` and `Action` apply to
+/// every element, input or action.
+const PROPERTIES: &[(&str, &str, Minor)] = &[
+    ("AdaptiveCard", "selectAction", 1),
+    ("AdaptiveCard", "verticalContentAlignment", 1),
+    ("AdaptiveCard", "minHeight", 2),
+    ("AdaptiveCard", "refresh", 4),
+    ("AdaptiveCard", "authentication", 4),
+    ("AdaptiveCard", "rtl", 5),
+    ("AdaptiveCard", "metadata", 6),
+    ("Element", "height", 1),
+    ("Element", "isVisible", 2),
+    ("Element", "requires", 2),
+    ("Element", "fallback", 2),
+    ("Input", "isRequired", 3),
+    ("Input", "errorMessage", 3),
+    ("Input", "label", 3),
+    ("Action", "iconUrl", 1),
+    ("Action", "style", 2),
+    ("Action", "fallback", 2),
+    ("Action", "requires", 2),
+    ("Action", "isEnabled", 5),
+    ("Action", "mode", 5),
+    ("Action", "tooltip", 5),
+    ("Action", "role", 6),
+    ("TextBlock", "fontType", 2),
+    ("TextBlock", "style", 5),
+    ("Image", "backgroundColor", 1),
+    ("Image", "selectAction", 1),
+    ("Image", "width", 1),
+    ("Container", "selectAction", 1),
+    ("Container", "verticalContentAlignment", 1),
+    ("Container", "bleed", 2),
+    ("Container", "backgroundImage", 2),
+    ("Container", "minHeight", 2),
+    ("Container", "rtl", 5),
+    ("Column", "selectAction", 1),
+    ("Column", "verticalContentAlignment", 1),
+    ("Column", "bleed", 2),
+    ("Column", "backgroundImage", 2),
+    ("Column", "minHeight", 2),
+    ("Column", "rtl", 5),
+    ("ColumnSet", "selectAction", 1),
+    ("ColumnSet", "style", 2),
+    ("ColumnSet", "bleed", 2),
+    ("ColumnSet", "horizontalAlignment", 2),
+    ("ColumnSet", "minHeight", 2),
+    ("Media", "captionSources", 6),
+    ("TextRun", "underline", 3),
+    ("Input.Text", "inlineAction", 2),
+    ("Input.Text", "regex", 3),
+    ("Input.Toggle", "wrap", 2),
+    ("Input.ChoiceSet", "wrap", 2),
+    ("Input.ChoiceSet", "choices.data", 6),
+    ("Action.Submit", "associatedInputs", 3),
+    ("Action.Execute", "associatedInputs", 3),
+];
+
+/// A construct used by the card and the version that introduced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Requirement {
+    /// JSON pointer of the element, action or property.
+    pub path: String,
+    /// `Table`, or `Input.Text.regex` for a property.
+    pub feature: String,
+    pub minor: Minor,
+}
+
+/// `"1.5"` or `"1.5.0"` → `5`; `None` for other majors or malformed versions.
+pub(crate) fn parse_version(version: &str) -> Option<Minor> {
+    let mut parts = version.trim().split('.');
+    if parts.next()? != "1" {
+        return None;
+    }
+    let minor = parts.next()?.parse().ok()?;
+    parts
+        .all(|patch| patch.parse::<u32>().is_ok())
+        .then_some(minor)
+}
+
+pub(crate) fn format_version(minor: Minor) -> String {
+    format!("1.{minor}")
+}
+
+/// Everything in the card introduced after 1.0. An element or action of a newer type is
+/// not listed when it declares a `fallback`, since older clients render that instead (the
+/// `fallback` property itself requires 1.2).
+pub(crate) fn requirements(card: &Value) -> Vec<Requirement> {
+    let mut found = Vec::new();
+    if let Some(map) = card.as_object() {
+        visit(map, "AdaptiveCard", "", &mut found);
+    }
+    found
+}
+
+/// The lowest version supporting everything the card uses.
+pub(crate) fn minimum_version(card: &Value) -> Minor {
+    requirements(card)
+        .iter()
+        .map(|requirement| requirement.minor)
+        .max()
+        .unwrap_or(0)
+}
+
+/// A `requires-newer-version` issue for each construct newer than the declared version.
+pub(crate) fn validate_versions(card: &Value) -> Vec<ValidationIssue> {
+    let Some(declared) = card
+        .get("version")
+        .and_then(Value::as_str)
+        .and_then(parse_version)
+    else {
+        return Vec::new();
+    };
+    requirements(card)
+        .into_iter()
+        .filter(|requirement| requirement.minor > declared)
+        .map(|requirement| ValidationIssue {
+            code: "requires-newer-version".into(),
+            message: format!(
+                "`{}` requires Adaptive Card {}, but the card declares version {}",
+                requirement.feature,
+                format_version(requirement.minor),
+                format_version(declared)
+            ),
+            path: if requirement.path.is_empty() {
+                "/".into()
+            } else {
+                requirement.path
+            },
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Version that introduced an element or action type; `None` for 1.0 and unknown types.
+pub(crate) fn type_version(kind: &str) -> Option<Minor> {
+    TYPES
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, minor)| *minor)
+}
+
+/// Version that introduced a property of the given element or action type; `None` for
+/// 1.0 and unknown properties.
+pub(crate) fn property_version(kind: &str, property: &str) -> Option<Minor> {
+    let groups: &[&str] = if kind == "AdaptiveCard" {
+        &[]
+    } else if kind.starts_with("Action.") {
+        &["Action"]
+    } else if kind.starts_with("Input.") {
+        &["Element", "Input"]
+    } else {
+        &["Element"]
+    };
+    PROPERTIES
+        .iter()
+        .filter(|(owner, name, _)| *name == property && (*owner == kind || groups.contains(owner)))
+        .map(|(_, _, minor)| *minor)
+        .max()
+}
+
+fn visit(map: &Map<String, Value>, kind: &str, path: &str, found: &mut Vec<Requirement>) {
+    if !map.contains_key("fallback")
+        && let Some(minor) = type_version(kind)
+    {
+        found.push(Requirement {
+            path: path.to_string(),
+            feature: kind.to_string(),
+            minor,
+        });
+    }
+    // Action `data` is passed to the host verbatim, so it is not card content.
+    for (key, value) in map.iter().filter(|(key, _)| *key != "data") {
+        let child_path = format!("{path}/{}", escape_pointer(key));
+        if let Some(minor) = property_version(kind, key) {
+            found.push(Requirement {
+                path: child_path.clone(),
+                feature: format!("{kind}.{key}"),
+                minor,
+            });
+        }
+        visit_child(value, kind, key, &child_path, found);
+    }
+}
+
+fn visit_child(value: &Value, parent: &str, key: &str, path: &str, found: &mut Vec<Requirement>) {
+    match value {
+        Value::Object(map) => {
+            let kind = map
+                .get("type")
+                .and_then(Value::as_str)
+                .or_else(|| implicit_type(parent, key));
+            match kind {
+                Some(kind) => visit(map, kind, path, found),
+                None => {
+                    for (child_key, child) in map.iter().filter(|(key, _)| *key != "data") {
+                        let child_path = format!("{path}/{}", escape_pointer(child_key));
+                        visit_child(child, parent, child_key, &child_path, found);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                visit_child(item, parent, key, &format!("{path}/{index}"), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Type of an untyped object from its position, for the collections that allow omitting
+/// `type`.
+pub(crate) fn implicit_type(parent: &str, key: &str) -> Option<&'static str> {
+    match (parent, key) {
+        ("ColumnSet", "columns") => Some("Column"),
+        ("ImageSet", "images") => Some("Image"),
+        ("Action.ShowCard", "card") => Some("AdaptiveCard"),
+        _ => None,
+    }
+}
+
+pub(crate) fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_constructs_newer_than_declared_version() {
+        let card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.2",
+            "body": [
+                { "type": "Table", "rows": [] },
+                { "type": "Table", "fallback": "drop" },
+                { "type": "Input.Text", "id": "name", "label": "Name" },
+                { "type": "ColumnSet", "columns": [ { "rtl": true, "items": [] } ] }
+            ],
+            "actions": [
+                { "type": "Action.Execute", "verb": "go" },
+                { "type": "Action.Submit", "data": { "type": "Table", "rtl": true } }
+            ]
+        });
+        let issues: Vec<(String, String)> = validate_versions(&card)
+            .into_iter()
+            .map(|issue| (issue.path, issue.message))
+            .collect();
+        let paths: Vec<&str> = issues.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/actions/0",
+                "/body/0",
+                "/body/2/label",
+                "/body/3/columns/0/rtl"
+            ]
+        );
+        assert_eq!(
+            issues[1].1,
+            "`Table` requires Adaptive Card 1.5, but the card declares version 1.2"
+        );
+        assert_eq!(format_version(minimum_version(&card)), "1.5");
+    }
+}
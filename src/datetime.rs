@@ -0,0 +1,550 @@
+/// Month and weekday names used by the `MMM`/`MMMM` and `ddd`/`dddd` tokens.
+#[derive(Debug)]
+pub(crate) struct Names {
+    pub months: [&'static str; 12],
+    pub months_short: [&'static str; 12],
+    /// Starting with Sunday.
+    pub days: [&'static str; 7],
+    pub days_short: [&'static str; 7],
+}
+
+pub(crate) const ENGLISH: Names = Names {
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    months_short: [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+    days: [
+        "Sunday",
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+    ],
+    days_short: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+};
+
+/// Default layout of timestamps produced by the date/time functions.
+pub(crate) const ISO_FORMAT: &str = "yyyy-MM-ddTHH:mm:ss.fffZ";
+
+/// Layout of local wall-clock timestamps, as returned by `convertFromUTC`.
+pub(crate) const LOCAL_ISO_FORMAT: &str = "yyyy-MM-ddTHH:mm:ss.fffffff";
+
+/// Bounds of the instants date arithmetic produces: 0000-01-01 up to 10000-01-01 (UTC),
+/// the years a four-digit `yyyy` can show.
+const MIN_MILLIS: i64 = -62_167_219_200_000;
+const MAX_MILLIS: i64 = 253_402_300_800_000;
+
+/// An instant with millisecond precision plus the UTC offset it should be displayed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct DateTime {
+    /// Milliseconds since the Unix epoch (UTC).
+    pub millis: i64,
+    /// Display offset from UTC in minutes.
+    pub offset_minutes: i32,
+}
+
+/// Broken-down local date and time fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Fields {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millis: u32,
+    /// 0 = Sunday.
+    pub weekday: u32,
+}
+
+impl DateTime {
+    /// Builds an instant from local `(year, month, day)` and `(hour, minute, second, millis)`.
+    pub fn from_local(
+        (year, month, day): (i64, u32, u32),
+        (hour, minute, second, millis): (u32, u32, u32, u32),
+        offset_minutes: i32,
+    ) -> Self {
+        let days = days_from_civil(year, month, day);
+        let local = days * 86_400_000
+            + i64::from(hour) * 3_600_000
+            + i64::from(minute) * 60_000
+            + i64::from(second) * 1000
+            + i64::from(millis);
+        DateTime {
+            millis: local - i64::from(offset_minutes) * 60_000,
+            offset_minutes,
+        }
+    }
+
+    /// Local fields in the display offset.
+    pub fn fields(&self) -> Fields {
+        let local = self.millis + i64::from(self.offset_minutes) * 60_000;
+        let days = local.div_euclid(86_400_000);
+        let ms_of_day = local.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days);
+        Fields {
+            year,
+            month,
+            day,
+            hour: (ms_of_day / 3_600_000) as u32,
+            minute: (ms_of_day / 60_000 % 60) as u32,
+            second: (ms_of_day / 1000 % 60) as u32,
+            millis: (ms_of_day % 1000) as u32,
+            weekday: (days + 4).rem_euclid(7) as u32,
+        }
+    }
+
+    pub fn from_millis(millis: i64) -> Self {
+        DateTime {
+            millis,
+            offset_minutes: 0,
+        }
+    }
+
+    pub fn with_offset(self, offset_minutes: i32) -> Self {
+        DateTime {
+            offset_minutes,
+            ..self
+        }
+    }
+
+    /// Shifts the instant, or `None` when the result falls outside years 0 to 9999.
+    pub fn add_millis(self, delta: i64) -> Option<Self> {
+        let millis = self
+            .millis
+            .checked_add(delta)
+            .filter(|millis| (MIN_MILLIS..MAX_MILLIS).contains(millis))?;
+        Some(DateTime { millis, ..self })
+    }
+
+    /// Adds calendar months, clamping the day to the end of the target month, or `None`
+    /// when the result falls outside years 0 to 9999.
+    pub fn add_months(self, months: i64) -> Option<Self> {
+        let f = self.fields();
+        let total = (f.year * 12 + i64::from(f.month - 1)).checked_add(months)?;
+        let year = total.div_euclid(12);
+        if !(0..=9999).contains(&year) {
+            return None;
+        }
+        let month = total.rem_euclid(12) as u32 + 1;
+        let day = f.day.min(days_in_month(year, month));
+        Some(DateTime::from_local(
+            (year, month, day),
+            (f.hour, f.minute, f.second, f.millis),
+            self.offset_minutes,
+        ))
+        .filter(|shifted| (MIN_MILLIS..MAX_MILLIS).contains(&shifted.millis))
+    }
+
+    pub fn day_of_year(&self) -> u32 {
+        let f = self.fields();
+        (days_from_civil(f.year, f.month, f.day) - days_from_civil(f.year, 1, 1) + 1) as u32
+    }
+}
+
+/// Parses `YYYY-MM-DD` optionally followed by `THH:MM[:SS[.fff]]` and `Z`/`±HH:MM`.
+/// Timestamps without an explicit offset are read as UTC.
+pub(crate) fn parse(input: &str) -> Option<DateTime> {
+    let s = input.trim();
+    let bytes = s.as_bytes();
+    let number = |range: std::ops::Range<usize>| -> Option<u32> {
+        let part = s.get(range)?;
+        part.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| part.parse().ok())?
+    };
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year = i64::from(number(0..4)?);
+    let month = number(5..7)?;
+    let day = number(8..10)?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    let (mut hour, mut minute, mut second, mut millis) = (0, 0, 0, 0);
+    let mut rest = &s[10..];
+    if let Some(time) = rest.strip_prefix(['T', 't', ' ']) {
+        let t = time.as_bytes();
+        if t.len() < 5 || t[2] != b':' {
+            return None;
+        }
+        hour = time.get(0..2)?.parse().ok()?;
+        minute = time.get(3..5)?.parse().ok()?;
+        rest = &time[5..];
+        if let Some(after) = rest.strip_prefix(':') {
+            second = after.get(0..2)?.parse().ok()?;
+            rest = &after[2..];
+            if let Some(frac) = rest.strip_prefix(['.', ',']) {
+                let digits = frac.bytes().take_while(u8::is_ascii_digit).count();
+                if digits == 0 {
+                    return None;
+                }
+                let padded = format!("{:0<3}", &frac[..digits.min(3)]);
+                millis = padded.parse().ok()?;
+                rest = &frac[digits..];
+            }
+        }
+        if hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+    }
+    let offset_minutes = match rest {
+        "" | "Z" | "z" => 0,
+        other => parse_offset(other)?,
+    };
+    Some(DateTime::from_local(
+        (year, month, day),
+        (hour, minute, second.min(59), millis),
+        offset_minutes,
+    ))
+}
+
+/// Parses `±HH:MM`, `±HHMM` or `±HH` into minutes east of UTC.
+pub(crate) fn parse_offset(raw: &str) -> Option<i32> {
+    let sign = match raw.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits: String = raw[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse::<i32>().ok()?, digits[2..].parse().ok()?),
+        _ => return None,
+    };
+    (hours <= 18 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+/// Renders a .NET-style custom format string, as used by the Adaptive Expressions
+/// date/time functions (`yyyy-MM-dd HH:mm`, `dddd d MMMM`, ...).
+/// Text in single or double quotes and `\`-escaped characters is copied verbatim.
+pub(crate) fn format(dt: &DateTime, pattern: &str) -> String {
+    format_with_names(dt, pattern, &ENGLISH)
+}
+
+pub(crate) fn format_with_names(dt: &DateTime, pattern: &str, names: &Names) -> String {
+    let f = dt.fields();
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let ch = chars[idx];
+        let run = chars[idx..].iter().take_while(|c| **c == ch).count();
+        match ch {
+            '\'' | '"' => {
+                let end = chars[idx + 1..]
+                    .iter()
+                    .position(|c| *c == ch)
+                    .map(|p| idx + 1 + p)
+                    .unwrap_or(chars.len());
+                out.extend(&chars[idx + 1..end]);
+                idx = end + 1;
+                continue;
+            }
+            '\\' => {
+                if let Some(next) = chars.get(idx + 1) {
+                    out.push(*next);
+                }
+                idx += 2;
+                continue;
+            }
+            'y' => {
+                if run <= 2 {
+                    out.push_str(&pad(f.year.rem_euclid(100) as u32, run));
+                } else {
+                    out.push_str(&format!("{:0width$}", f.year, width = run));
+                }
+            }
+            'M' => match run {
+                1 => out.push_str(&f.month.to_string()),
+                2 => out.push_str(&format!("{:02}", f.month)),
+                3 => out.push_str(names.months_short[f.month as usize - 1]),
+                _ => out.push_str(names.months[f.month as usize - 1]),
+            },
+            'd' => match run {
+                1 => out.push_str(&f.day.to_string()),
+                2 => out.push_str(&format!("{:02}", f.day)),
+                3 => out.push_str(names.days_short[f.weekday as usize]),
+                _ => out.push_str(names.days[f.weekday as usize]),
+            },
+            'H' => out.push_str(&pad(f.hour, run)),
+            'h' => out.push_str(&pad((f.hour + 11) % 12 + 1, run)),
+            'm' => out.push_str(&pad(f.minute, run)),
+            's' => out.push_str(&pad(f.second, run)),
+            'f' | 'F' => {
+                let digits = format!("{:03}", f.millis);
+                let mut frac: String = digits.chars().chain("0000".chars()).take(run).collect();
+                if ch == 'F' {
+                    frac = frac.trim_end_matches('0').to_string();
+                }
+                out.push_str(&frac);
+            }
+            't' => {
+                let marker = if f.hour < 12 { "AM" } else { "PM" };
+                out.push_str(&marker[..run.min(2)]);
+            }
+            'z' => {
+                let sign = if dt.offset_minutes < 0 { '-' } else { '+' };
+                let abs = dt.offset_minutes.unsigned_abs();
+                match run {
+                    1 => out.push_str(&format!("{sign}{}", abs / 60)),
+                    2 => out.push_str(&format!("{sign}{:02}", abs / 60)),
+                    _ => out.push_str(&format!("{sign}{:02}:{:02}", abs / 60, abs % 60)),
+                }
+            }
+            'K' => {
+                if dt.offset_minutes == 0 {
+                    out.push('Z');
+                } else {
+                    let sign = if dt.offset_minutes < 0 { '-' } else { '+' };
+                    let abs = dt.offset_minutes.unsigned_abs();
+                    out.push_str(&format!("{sign}{:02}:{:02}", abs / 60, abs % 60));
+                }
+            }
+            other => {
+                for _ in 0..run {
+                    out.push(other);
+                }
+            }
+        }
+        idx += run;
+    }
+    out
+}
+
+fn pad(value: u32, run: usize) -> String {
+    if run >= 2 {
+        format!("{value:02}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Daylight saving rules of the built-in time zones.
+#[derive(Clone, Copy)]
+enum Dst {
+    None,
+    /// Last Sunday of March to last Sunday of October, switching at 01:00 UTC.
+    Europe,
+    /// Second Sunday of March to first Sunday of November, at 02:00 local time.
+    NorthAmerica,
+    /// First Sunday of October to first Sunday of April, at 02:00 standard time.
+    SouthEastAustralia,
+    /// Last Sunday of September to first Sunday of April, at 02:00 standard time.
+    NewZealand,
+}
+
+/// Zones with their standard offset in minutes and current DST rule.
+const ZONES: &[(&str, i32, Dst)] = &[
+    ("UTC", 0, Dst::None),
+    ("Etc/UTC", 0, Dst::None),
+    ("GMT", 0, Dst::None),
+    ("Europe/London", 0, Dst::Europe),
+    ("Europe/Dublin", 0, Dst::Europe),
+    ("Europe/Lisbon", 0, Dst::Europe),
+    ("Europe/Amsterdam", 60, Dst::Europe),
+    ("Europe/Brussels", 60, Dst::Europe),
+    ("Europe/Luxembourg", 60, Dst::Europe),
+    ("Europe/Paris", 60, Dst::Europe),
+    ("Europe/Berlin", 60, Dst::Europe),
+    ("Europe/Vienna", 60, Dst::Europe),
+    ("Europe/Zurich", 60, Dst::Europe),
+    ("Europe/Madrid", 60, Dst::Europe),
+    ("Europe/Rome", 60, Dst::Europe),
+    ("Europe/Copenhagen", 60, Dst::Europe),
+    ("Europe/Oslo", 60, Dst::Europe),
+    ("Europe/Stockholm", 60, Dst::Europe),
+    ("Europe/Warsaw", 60, Dst::Europe),
+    ("Europe/Prague", 60, Dst::Europe),
+    ("Europe/Budapest", 60, Dst::Europe),
+    ("Europe/Helsinki", 120, Dst::Europe),
+    ("Europe/Athens", 120, Dst::Europe),
+    ("Europe/Bucharest", 120, Dst::Europe),
+    ("Europe/Kyiv", 120, Dst::Europe),
+    ("Europe/Istanbul", 180, Dst::None),
+    ("Europe/Moscow", 180, Dst::None),
+    ("America/New_York", -300, Dst::NorthAmerica),
+    ("America/Toronto", -300, Dst::NorthAmerica),
+    ("America/Chicago", -360, Dst::NorthAmerica),
+    ("America/Denver", -420, Dst::NorthAmerica),
+    ("America/Phoenix", -420, Dst::None),
+    ("America/Los_Angeles", -480, Dst::NorthAmerica),
+    ("America/Vancouver", -480, Dst::NorthAmerica),
+    ("America/Anchorage", -540, Dst::NorthAmerica),
+    ("Pacific/Honolulu", -600, Dst::None),
+    ("America/Mexico_City", -360, Dst::None),
+    ("America/Sao_Paulo", -180, Dst::None),
+    ("America/Argentina/Buenos_Aires", -180, Dst::None),
+    ("Africa/Johannesburg", 120, Dst::None),
+    ("Africa/Lagos", 60, Dst::None),
+    ("Africa/Cairo", 120, Dst::None),
+    ("Asia/Dubai", 240, Dst::None),
+    ("Asia/Kolkata", 330, Dst::None),
+    ("Asia/Bangkok", 420, Dst::None),
+    ("Asia/Jakarta", 420, Dst::None),
+    ("Asia/Singapore", 480, Dst::None),
+    ("Asia/Hong_Kong", 480, Dst::None),
+    ("Asia/Shanghai", 480, Dst::None),
+    ("Asia/Tokyo", 540, Dst::None),
+    ("Asia/Seoul", 540, Dst::None),
+    ("Australia/Perth", 480, Dst::None),
+    ("Australia/Brisbane", 600, Dst::None),
+    ("Australia/Sydney", 600, Dst::SouthEastAustralia),
+    ("Australia/Melbourne", 600, Dst::SouthEastAustralia),
+    ("Pacific/Auckland", 720, Dst::NewZealand),
+    // Windows names accepted by Adaptive Expressions.
+    ("GMT Standard Time", 0, Dst::Europe),
+    ("W. Europe Standard Time", 60, Dst::Europe),
+    ("Romance Standard Time", 60, Dst::Europe),
+    ("Central Europe Standard Time", 60, Dst::Europe),
+    ("FLE Standard Time", 120, Dst::Europe),
+    ("Eastern Standard Time", -300, Dst::NorthAmerica),
+    ("Central Standard Time", -360, Dst::NorthAmerica),
+    ("Mountain Standard Time", -420, Dst::NorthAmerica),
+    ("Pacific Standard Time", -480, Dst::NorthAmerica),
+    ("India Standard Time", 330, Dst::None),
+    ("China Standard Time", 480, Dst::None),
+    ("Tokyo Standard Time", 540, Dst::None),
+    ("AUS Eastern Standard Time", 600, Dst::SouthEastAustralia),
+];
+
+/// A display zone: a built-in IANA zone, `UTC`/`Z`, or a fixed `±HH:MM` offset.
+#[derive(Clone, Copy)]
+pub(crate) struct TimeZone {
+    standard: i32,
+    dst: Dst,
+}
+
+impl TimeZone {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("z") || name.eq_ignore_ascii_case("utc") {
+            return Some(TimeZone {
+                standard: 0,
+                dst: Dst::None,
+            });
+        }
+        if let Some(standard) = parse_offset(name) {
+            return Some(TimeZone {
+                standard,
+                dst: Dst::None,
+            });
+        }
+        ZONES
+            .iter()
+            .find(|(zone, _, _)| zone.eq_ignore_ascii_case(name))
+            .map(|&(_, standard, dst)| TimeZone { standard, dst })
+    }
+
+    /// Offset from UTC, in minutes, at the given instant.
+    pub(crate) fn offset_at(&self, utc_millis: i64) -> i32 {
+        let year = DateTime::from_millis(utc_millis).fields().year;
+        let std = i64::from(self.standard) * 60_000;
+        // Transition instants in UTC milliseconds.
+        let at = |(month, sunday): (u32, i32), utc_hour: i64| {
+            days_from_civil(year, month, nth_sunday(year, month, sunday)) * 86_400_000
+                + utc_hour * 3_600_000
+        };
+        let in_dst = match self.dst {
+            Dst::None => false,
+            Dst::Europe => (at((3, -1), 1)..at((10, -1), 1)).contains(&utc_millis),
+            Dst::NorthAmerica => {
+                (at((3, 2), 2) - std..at((11, 1), 2) - std - 3_600_000).contains(&utc_millis)
+            }
+            Dst::SouthEastAustralia => {
+                utc_millis >= at((10, 1), 2) - std || utc_millis < at((4, 1), 2) - std
+            }
+            Dst::NewZealand => {
+                utc_millis >= at((9, -1), 2) - std || utc_millis < at((4, 1), 2) - std
+            }
+        };
+        self.standard + if in_dst { 60 } else { 0 }
+    }
+
+    /// The instant shown in this zone.
+    pub(crate) fn localize(&self, dt: DateTime) -> DateTime {
+        dt.with_offset(self.offset_at(dt.millis))
+    }
+
+    /// Reads the wall-clock fields of `dt` (ignoring its offset) as local time in this
+    /// zone, returning the UTC instant.
+    pub(crate) fn resolve_wall_clock(&self, dt: DateTime) -> DateTime {
+        let wall = dt.millis + i64::from(dt.offset_minutes) * 60_000;
+        let guess = wall - i64::from(self.standard) * 60_000;
+        let offset = self.offset_at(guess);
+        DateTime {
+            millis: wall - i64::from(offset) * 60_000,
+            offset_minutes: offset,
+        }
+    }
+}
+
+/// Day of the month of the `n`th Sunday (`-1` for the last one).
+fn nth_sunday(year: i64, month: u32, n: i32) -> u32 {
+    let sunday_on_or_after = |day: u32| {
+        let weekday = (days_from_civil(year, month, day) + 4).rem_euclid(7) as u32;
+        day + (7 - weekday) % 7
+    };
+    if n < 0 {
+        let last = days_in_month(year, month);
+        sunday_on_or_after(last - 6)
+    } else {
+        sunday_on_or_after(1) + 7 * (n as u32 - 1)
+    }
+}
+
+pub(crate) fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub(crate) fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if is_leap_year(year) => 29,
+        _ => 28,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
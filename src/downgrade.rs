@@ -0,0 +1,358 @@
+use serde_json::{Map, Value, json};
+
+use crate::compatibility::{self, Minor, escape_pointer, format_version};
+use crate::host_profile::HostCapabilities;
+use crate::model::CardTransformation;
+use crate::url_safety::NodeMove;
+
+/// Keys holding elements or actions; other objects (such as `data`) are never rewritten.
+const STRUCTURAL_KEYS: &[&str] = &[
+    "body",
+    "items",
+    "actions",
+    "columns",
+    "card",
+    "selectAction",
+    "inlineAction",
+    "images",
+    "rows",
+    "cells",
+    "inlines",
+    "refresh",
+    "action",
+];
+
+/// What the downgraded card must run on.
+pub(crate) struct DowngradeTarget<'h> {
+    pub version: Minor,
+    pub host: Option<&'h HostCapabilities>,
+}
+
+impl DowngradeTarget<'_> {
+    fn supports(&self, kind: &str) -> bool {
+        if kind == "Action.Execute" && !self.universal_actions() {
+            return false;
+        }
+        let known = compatibility::type_version(kind).is_none_or(|minor| minor <= self.version);
+        let hosted = self.host.is_none_or(|host| {
+            if kind.starts_with("Action.") {
+                host.supports_action(kind)
+            } else {
+                host.supports_element(kind)
+            }
+        });
+        known && hosted
+    }
+
+    fn universal_actions(&self) -> bool {
+        self.version >= 4 && self.host.is_none_or(|host| host.action_execute)
+    }
+}
+
+/// Rewrites the card so it renders on `target`: unsupported elements and actions are
+/// replaced by their `fallback` (or removed for `"drop"`), `Action.Execute` without a
+/// fallback becomes `Action.Submit` carrying its verb in `data.verb`, properties newer than
+/// the target version are removed, and a newer `version` is lowered. Unsupported nodes
+/// without a fallback are left for validation to report. Where each node went is added to
+/// `moves`.
+pub(crate) fn downgrade(
+    card: &mut Value,
+    target: &DowngradeTarget,
+    moves: &mut Vec<NodeMove>,
+) -> Vec<CardTransformation> {
+    let mut pass = Pass {
+        target,
+        log: Vec::new(),
+        moves,
+    };
+    let Some(map) = card.as_object_mut() else {
+        return pass.log;
+    };
+    let root = Place {
+        path: String::new(),
+        from: String::new(),
+        to: String::new(),
+    };
+    pass.kept(&root);
+    visit(map, "AdaptiveCard", &root, &mut pass);
+    let declared = map
+        .get("version")
+        .and_then(Value::as_str)
+        .and_then(compatibility::parse_version);
+    if let Some(declared) = declared
+        && declared > target.version
+    {
+        let lowered = format_version(target.version);
+        pass.log.push(CardTransformation {
+            code: "version-lowered".into(),
+            message: format!(
+                "version lowered from {} to {lowered}",
+                format_version(declared)
+            ),
+            path: "/version".into(),
+        });
+        map.insert("version".into(), Value::String(lowered));
+    }
+    pass.log
+}
+
+struct Pass<'a, 'h> {
+    target: &'a DowngradeTarget<'h>,
+    log: Vec<CardTransformation>,
+    moves: &'a mut Vec<NodeMove>,
+}
+
+impl Pass<'_, '_> {
+    fn kept(&mut self, place: &Place) {
+        self.moves.push(NodeMove {
+            from: place.from.clone(),
+            to: Some(place.to.clone()),
+        });
+    }
+
+    fn removed(&mut self, from: String) {
+        self.moves.push(NodeMove { from, to: None });
+    }
+}
+
+/// Where a node is: `path` as reported in transformations (its position before
+/// downgrading, with applied fallbacks taking their node's place), `from` its exact
+/// pointer before downgrading, and `to` its pointer afterwards.
+struct Place {
+    path: String,
+    from: String,
+    to: String,
+}
+
+impl Place {
+    fn child(&self, key: &str, index: Option<(usize, usize)>) -> Place {
+        match index {
+            Some((before, after)) => Place {
+                path: format!("{}/{key}/{before}", self.path),
+                from: format!("{}/{key}/{before}", self.from),
+                to: format!("{}/{key}/{after}", self.to),
+            },
+            None => Place {
+                path: format!("{}/{key}", self.path),
+                from: format!("{}/{key}", self.from),
+                to: format!("{}/{key}", self.to),
+            },
+        }
+    }
+}
+
+fn visit(map: &mut Map<String, Value>, kind: &str, place: &Place, pass: &mut Pass<'_, '_>) {
+    let newer: Vec<String> = map
+        .keys()
+        .filter(|key| {
+            compatibility::property_version(kind, key)
+                .is_some_and(|minor| minor > pass.target.version)
+        })
+        .cloned()
+        .collect();
+    for key in newer {
+        map.remove(&key);
+        let key_path = escape_pointer(&key);
+        pass.removed(format!("{}/{key_path}", place.from));
+        pass.log.push(CardTransformation {
+            code: "property-removed".into(),
+            message: format!(
+                "`{kind}.{key}` removed; it requires Adaptive Card {}",
+                format_version(compatibility::property_version(kind, &key).unwrap_or_default())
+            ),
+            path: format!("{}/{key_path}", place.path),
+        });
+    }
+
+    for key in STRUCTURAL_KEYS {
+        let Some(child) = map.get_mut(*key) else {
+            continue;
+        };
+        match child {
+            Value::Array(items) => {
+                let original = std::mem::take(items);
+                for (index, item) in original.into_iter().enumerate() {
+                    let item_place = place.child(key, Some((index, items.len())));
+                    if let Some(item) = resolve(item, kind, key, item_place, pass) {
+                        items.push(item);
+                    }
+                }
+            }
+            Value::Object(_) => {
+                let value = child.take();
+                match resolve(value, kind, key, place.child(key, None), pass) {
+                    Some(value) => *child = value,
+                    None => {
+                        map.remove(*key);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Applies fallbacks until the node is supported (or has none left), then downgrades its
+/// contents. `None` when the node is dropped.
+fn resolve(
+    mut value: Value,
+    parent: &str,
+    key: &str,
+    mut place: Place,
+    pass: &mut Pass<'_, '_>,
+) -> Option<Value> {
+    loop {
+        let Some(map) = value.as_object_mut() else {
+            pass.kept(&place);
+            return Some(value);
+        };
+        let Some(kind) = map
+            .get("type")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| compatibility::implicit_type(parent, key).map(str::to_string))
+        else {
+            pass.kept(&place);
+            return Some(value);
+        };
+        if pass.target.supports(&kind) {
+            pass.kept(&place);
+            visit(map, &kind, &place, pass);
+            return Some(value);
+        }
+        match map.remove("fallback") {
+            Some(Value::String(text)) if text.eq_ignore_ascii_case("drop") => {
+                pass.removed(place.from.clone());
+                pass.log.push(CardTransformation {
+                    code: "element-dropped".into(),
+                    message: format!("`{kind}` is not supported and its fallback is \"drop\""),
+                    path: place.path.clone(),
+                });
+                return None;
+            }
+            Some(fallback @ Value::Object(_)) => {
+                let replacement = fallback
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("element")
+                    .to_string();
+                pass.removed(place.from.clone());
+                pass.log.push(CardTransformation {
+                    code: "fallback-applied".into(),
+                    message: format!("`{kind}` replaced by its fallback `{replacement}`"),
+                    path: place.path.clone(),
+                });
+                place.from.push_str("/fallback");
+                value = fallback;
+            }
+            _ if kind == "Action.Execute" => {
+                rewrite_execute(map);
+                pass.log.push(CardTransformation {
+                    code: "action-rewritten".into(),
+                    message: "`Action.Execute` rewritten to `Action.Submit` with `data.verb`"
+                        .into(),
+                    path: place.path.clone(),
+                });
+            }
+            other => {
+                if let Some(fallback) = other {
+                    map.insert("fallback".into(), fallback);
+                }
+                pass.kept(&place);
+                visit(map, &kind, &place, pass);
+                return Some(value);
+            }
+        }
+    }
+}
+
+fn rewrite_execute(map: &mut Map<String, Value>) {
+    map.insert("type".into(), json!("Action.Submit"));
+    let Some(verb) = map.remove("verb") else {
+        return;
+    };
+    match map.get_mut("data") {
+        Some(Value::Object(data)) => {
+            data.entry("verb").or_insert(verb);
+        }
+        None | Some(Value::Null) => {
+            map.insert("data".into(), json!({ "verb": verb }));
+        }
+        Some(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host_profile;
+    use crate::model::HostProfile;
+
+    #[test]
+    fn applies_fallbacks_and_rewrites_execute_for_webex() {
+        let mut card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.5",
+            "body": [
+                { "type": "Table", "rows": [], "fallback": { "type": "TextBlock", "text": "See report" } },
+                { "type": "Media", "sources": [], "fallback": "drop" },
+                { "type": "Input.Text", "id": "note", "label": "Note", "isRequired": true }
+            ],
+            "actions": [
+                { "type": "Action.Execute", "title": "Approve", "verb": "approve", "data": { "id": 7 } }
+            ]
+        });
+        let host = host_profile::capabilities(&HostProfile::Webex);
+        let target = DowngradeTarget {
+            version: host.max_version,
+            host: Some(host),
+        };
+        let log = downgrade(&mut card, &target, &mut Vec::new());
+        assert_eq!(
+            card,
+            json!({
+                "type": "AdaptiveCard",
+                "version": "1.3",
+                "body": [
+                    { "type": "TextBlock", "text": "See report" },
+                    { "type": "Input.Text", "id": "note", "label": "Note", "isRequired": true }
+                ],
+                "actions": [
+                    { "type": "Action.Submit", "title": "Approve", "data": { "id": 7, "verb": "approve" } }
+                ]
+            })
+        );
+        let codes: Vec<(&str, &str)> = log
+            .iter()
+            .map(|entry| (entry.code.as_str(), entry.path.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("fallback-applied", "/body/0"),
+                ("element-dropped", "/body/1"),
+                ("action-rewritten", "/actions/0"),
+                ("version-lowered", "/version"),
+            ]
+        );
+
+        let mut older = json!({
+            "type": "AdaptiveCard",
+            "version": "1.3",
+            "body": [ { "type": "Input.Text", "id": "note", "label": "Note" } ]
+        });
+        let log = downgrade(
+            &mut older,
+            &DowngradeTarget {
+                version: 2,
+                host: None,
+            },
+            &mut Vec::new(),
+        );
+        assert_eq!(
+            older["body"][0],
+            json!({ "type": "Input.Text", "id": "note" })
+        );
+        assert_eq!(log[0].path, "/body/0/label");
+    }
+}
@@ -1,112 +1,561 @@
-use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
 
+use once_cell::sync::OnceCell;
+use serde_json::{Map, Number, Value};
+
+use crate::error::ComponentError;
+use crate::expression_functions;
+use crate::expression_parser::{self, BinaryOp, Expr, UnaryOp};
 use crate::render::BindingContext;
 
-/// Minimal expression engine interface so richer engines can be plugged in later.
+/// Expression engine interface; hosts can plug in their own with `register_expression_engine`.
 pub trait ExpressionEngine: Send + Sync {
     /// Evaluate an expression string against the binding context.
     /// Returns None on invalid expressions or when no resolution is possible.
     fn eval(&self, expr: &str, ctx: &BindingContext) -> Option<Value>;
+
+    /// Evaluate an expression, keeping unresolved paths (`Ok(None)`) apart from malformed
+    /// expressions, which are reported as binding errors.
+    fn try_eval(&self, expr: &str, ctx: &BindingContext) -> Result<Option<Value>, ComponentError> {
+        Ok(self.eval(expr, ctx))
+    }
 }
 
-/// Default lightweight engine supporting:
-/// - Path lookups (payload/session/state/params) via dotted notation.
-/// - Equality on scalar values using `==`.
-/// - Simple ternary `cond ? a : b`.
-/// - Graceful failure: returns None for unknown expressions or missing paths.
+/// Default engine backed by a tokenizer and precedence-climbing parser supporting:
+/// - Path lookups (payload/session/state/params and templating scopes) with member and
+///   index access, e.g. `payload.items[0].name`.
+/// - Literals: numbers, single/double-quoted strings with escapes, `true`/`false`/`null`.
+/// - Operators, loosest first: `?:`, `||`, `&&`, `== !=`, `< <= > >=`, `+ -`, `* / %`,
+///   unary `!`/`-`, plus parentheses.
+/// - Function calls from the Adaptive Expressions built-in library (string, math,
+///   collection, logical, conversion and date/time), e.g. `toUpper(payload.name)`, and
+///   functions registered with `register_host_function`.
+/// - `||`/`&&` return the deciding operand, so `payload.name || "Guest"` picks a default.
+/// - Boolean contexts (`!`, `&&`, `||`, `==`, `!=`, ternary conditions) treat missing paths
+///   as null; anywhere else a missing path leaves the expression unresolved.
 #[derive(Default)]
 pub struct SimpleExpressionEngine;
 
 impl ExpressionEngine for SimpleExpressionEngine {
     fn eval(&self, expr: &str, ctx: &BindingContext) -> Option<Value> {
-        let trimmed = expr.trim();
-        // Ternary: cond ? a : b
-        if let Some((cond_raw, rest)) = split_top_level(trimmed, '?') {
-            let (then_raw, else_raw) = split_top_level(rest, ':')?;
-            let cond_val = self.eval(cond_raw.trim(), ctx)?;
-            let branch = if truthy(&cond_val) {
-                self.eval(then_raw.trim(), ctx)?
-            } else {
-                self.eval(else_raw.trim(), ctx)?
-            };
-            return Some(branch);
-        }
+        self.try_eval(expr, ctx).ok().flatten()
+    }
+
+    fn try_eval(&self, expr: &str, ctx: &BindingContext) -> Result<Option<Value>, ComponentError> {
+        run(expr, ctx, Dialect::Simple)
+    }
+}
+
+/// Engine following Adaptive Expressions semantics on the same grammar and function
+/// library as [`SimpleExpressionEngine`]:
+/// - `||`/`&&` always produce booleans (use `coalesce` for defaults).
+/// - Integer division truncates (`7 / 2` is `3`).
+/// - Function arguments that reference missing paths are passed as null.
+#[derive(Default)]
+pub struct AdaptiveExpressionEngine;
+
+impl ExpressionEngine for AdaptiveExpressionEngine {
+    fn eval(&self, expr: &str, ctx: &BindingContext) -> Option<Value> {
+        self.try_eval(expr, ctx).ok().flatten()
+    }
+
+    fn try_eval(&self, expr: &str, ctx: &BindingContext) -> Result<Option<Value>, ComponentError> {
+        run(expr, ctx, Dialect::Adaptive)
+    }
+}
+
+/// Evaluation rules that differ between the built-in engines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    Simple,
+    Adaptive,
+}
 
-        // Equality
-        if let Some((left, right)) = split_equality(trimmed) {
-            let l = eval_atom(left.trim(), ctx)?;
-            let r = eval_atom(right.trim(), ctx)?;
-            return Some(Value::Bool(equals(&l, &r)));
+fn run(
+    expr: &str,
+    ctx: &BindingContext,
+    dialect: Dialect,
+) -> Result<Option<Value>, ComponentError> {
+    let ast = expression_parser::parse(expr).map_err(|err| {
+        ComponentError::Binding(format!(
+            "parse error at column {}: {} in `{expr}`",
+            err.column, err.message
+        ))
+    })?;
+    match evaluate(&ast, ctx, dialect) {
+        Ok(value) => Ok(Some(value)),
+        Err(EvalError::Missing) => Ok(None),
+        Err(EvalError::Invalid(message)) => {
+            Err(ComponentError::Binding(format!("{message} in `{expr}`")))
         }
+    }
+}
+
+/// Name of the engine used when neither the invocation nor the host picks one.
+const DEFAULT_ENGINE: &str = "simple";
+
+#[derive(Default)]
+struct EngineRegistry {
+    engines: BTreeMap<String, Arc<dyn ExpressionEngine>>,
+    default: Option<String>,
+}
+
+static ENGINES: OnceCell<RwLock<EngineRegistry>> = OnceCell::new();
+
+fn engine_cell() -> &'static RwLock<EngineRegistry> {
+    ENGINES.get_or_init(|| RwLock::new(EngineRegistry::default()))
+}
 
-        eval_atom(trimmed, ctx)
+/// Registers an engine that invocations can select with `card_spec.expression_engine`.
+/// Registering `simple` or `adaptive` replaces the built-in engine of that name.
+pub fn register_expression_engine(
+    name: &str,
+    engine: Box<dyn ExpressionEngine>,
+) -> Result<(), &'static str> {
+    if name.trim().is_empty() {
+        return Err("engine name must not be empty");
     }
+    engine_cell()
+        .write()
+        .map_err(|_| "expression engines poisoned")?
+        .engines
+        .insert(name.to_string(), Arc::from(engine));
+    Ok(())
 }
 
-fn eval_atom(expr: &str, ctx: &BindingContext) -> Option<Value> {
-    // Path forms: @{path} or ${path} or bare path
-    if let Some(path) = expr.strip_prefix("@{").and_then(|s| s.strip_suffix('}')) {
-        return ctx.lookup(path.trim());
+/// Sets the engine used by invocations that do not name one. The engine must be built-in
+/// or already registered.
+pub fn set_default_expression_engine(name: &str) -> Result<(), &'static str> {
+    let mut registry = engine_cell()
+        .write()
+        .map_err(|_| "expression engines poisoned")?;
+    if !registry.engines.contains_key(name) && builtin_engine(name).is_none() {
+        return Err("unknown expression engine");
     }
-    if let Some(path) = expr.strip_prefix("${").and_then(|s| s.strip_suffix('}')) {
-        return ctx.lookup(path.trim());
+    registry.default = Some(name.to_string());
+    Ok(())
+}
+
+fn builtin_engine(name: &str) -> Option<Arc<dyn ExpressionEngine>> {
+    match name {
+        "simple" => Some(Arc::new(SimpleExpressionEngine)),
+        "adaptive" => Some(Arc::new(AdaptiveExpressionEngine)),
+        _ => None,
     }
-    if expr.starts_with('@') || expr.starts_with('$') {
-        return ctx.lookup(expr.trim_start_matches(&['@', '$'][..]));
+}
+
+/// Resolves the engine requested by an invocation, falling back to the host default and
+/// then to [`DEFAULT_ENGINE`]. Returns the effective engine name alongside the engine.
+pub(crate) fn resolve_engine(
+    requested: Option<&str>,
+) -> Result<(String, Arc<dyn ExpressionEngine>), ComponentError> {
+    let registry = engine_cell()
+        .read()
+        .map_err(|_| ComponentError::Binding("expression engines poisoned".into()))?;
+    let name = requested
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .or(registry.default.as_deref())
+        .unwrap_or(DEFAULT_ENGINE)
+        .to_string();
+    let engine = registry
+        .engines
+        .get(&name)
+        .cloned()
+        .or_else(|| builtin_engine(&name))
+        .ok_or_else(|| {
+            ComponentError::InvalidInput(format!("unknown expression engine `{name}`"))
+        })?;
+    Ok((name, engine))
+}
+
+#[derive(Debug)]
+pub(crate) enum EvalError {
+    /// A path did not resolve against the binding context.
+    Missing,
+    /// The expression is well-formed but cannot be evaluated (e.g. type mismatch).
+    Invalid(String),
+}
+
+pub(crate) fn evaluate(
+    expr: &Expr,
+    ctx: &BindingContext,
+    dialect: Dialect,
+) -> Result<Value, EvalError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Identifier(_) | Expr::Member(..) | Expr::Index(..) => {
+            resolve_path(expr, ctx, dialect)
+        }
+        Expr::Unary(UnaryOp::Not, inner) => {
+            let value = or_null(evaluate(inner, ctx, dialect))?;
+            Ok(Value::Bool(!truthy(&value)))
+        }
+        Expr::Unary(UnaryOp::Negate, inner) => match as_number(&evaluate(inner, ctx, dialect)?) {
+            Some(Num::Int(i)) => Ok(i
+                .checked_neg()
+                .map(Value::from)
+                .unwrap_or_else(|| float_value(-(i as f64)))),
+            Some(Num::Float(f)) => Ok(float_value(-f)),
+            None => Err(EvalError::Invalid("cannot negate a non-number".into())),
+        },
+        Expr::Binary(op, left, right) => eval_binary(*op, left, right, ctx, dialect),
+        Expr::Conditional(condition, then_branch, else_branch) => {
+            if truthy(&or_null(evaluate(condition, ctx, dialect))?) {
+                evaluate(then_branch, ctx, dialect)
+            } else {
+                evaluate(else_branch, ctx, dialect)
+            }
+        }
+        Expr::Call(name, args) => call_function(name, args, ctx, dialect),
     }
+}
+
+/// Functions whose arguments may be missing paths; those arrive as null.
+const NULL_TOLERANT: &[&str] = &[
+    "empty",
+    "equals",
+    "not",
+    "string",
+    "concat",
+    "isString",
+    "isInteger",
+    "isFloat",
+    "isArray",
+    "isObject",
+    "isBoolean",
+    "isDateTime",
+];
 
-    // Literals
-    if let Ok(n) = expr.parse::<f64>() {
-        return serde_json::Number::from_f64(n).map(Value::Number);
+fn call_function(
+    name: &str,
+    args: &[Expr],
+    ctx: &BindingContext,
+    dialect: Dialect,
+) -> Result<Value, EvalError> {
+    match name {
+        "if" => {
+            let [condition, then_branch, else_branch] = args else {
+                return Err(EvalError::Invalid(format!(
+                    "if() expects 3 argument(s) but got {}",
+                    args.len()
+                )));
+            };
+            if truthy(&or_null(evaluate(condition, ctx, dialect))?) {
+                evaluate(then_branch, ctx, dialect)
+            } else {
+                evaluate(else_branch, ctx, dialect)
+            }
+        }
+        "and" => {
+            for arg in args {
+                if !truthy(&or_null(evaluate(arg, ctx, dialect))?) {
+                    return Ok(Value::Bool(false));
+                }
+            }
+            Ok(Value::Bool(true))
+        }
+        "or" => {
+            for arg in args {
+                if truthy(&or_null(evaluate(arg, ctx, dialect))?) {
+                    return Ok(Value::Bool(true));
+                }
+            }
+            Ok(Value::Bool(false))
+        }
+        "coalesce" => {
+            for arg in args {
+                let value = or_null(evaluate(arg, ctx, dialect))?;
+                if !value.is_null() {
+                    return Ok(value);
+                }
+            }
+            Ok(Value::Null)
+        }
+        "exists" => {
+            let [arg] = args else {
+                return Err(EvalError::Invalid(format!(
+                    "exists() expects 1 argument(s) but got {}",
+                    args.len()
+                )));
+            };
+            Ok(Value::Bool(
+                !or_null(evaluate(arg, ctx, dialect))?.is_null(),
+            ))
+        }
+        "t" => {
+            let values = args
+                .iter()
+                .map(|arg| or_null(evaluate(arg, ctx, dialect)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let Some((Value::String(key), rest)) = values.split_first() else {
+                return Err(EvalError::Invalid(
+                    "t() expects a string key as its first argument".into(),
+                ));
+            };
+            let args = match rest {
+                [Value::Object(map)] => map.clone(),
+                pairs if pairs.len() % 2 == 0 => {
+                    let mut map = Map::new();
+                    for pair in pairs.chunks(2) {
+                        let Value::String(name) = &pair[0] else {
+                            return Err(EvalError::Invalid(
+                                "t() expects an object or name/value pairs after the key".into(),
+                            ));
+                        };
+                        map.insert(name.clone(), pair[1].clone());
+                    }
+                    map
+                }
+                _ => {
+                    return Err(EvalError::Invalid(
+                        "t() expects an object or name/value pairs after the key".into(),
+                    ));
+                }
+            };
+            Ok(Value::String(ctx.translate(key, &args)))
+        }
+        _ => {
+            let tolerant = dialect == Dialect::Adaptive || NULL_TOLERANT.contains(&name);
+            let values = args
+                .iter()
+                .map(|arg| {
+                    let value = evaluate(arg, ctx, dialect);
+                    if tolerant { or_null(value) } else { value }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match expression_functions::call_function(name, &values, ctx.locale()) {
+                Some(result) => {
+                    result.map_err(|msg| EvalError::Invalid(format!("{name}(): {msg}")))
+                }
+                None => Err(EvalError::Invalid(format!("unknown function `{name}`"))),
+            }
+        }
     }
-    if expr.eq_ignore_ascii_case("true") {
-        return Some(Value::Bool(true));
+}
+
+/// Boolean contexts read unresolved paths as null instead of failing.
+fn or_null(result: Result<Value, EvalError>) -> Result<Value, EvalError> {
+    match result {
+        Err(EvalError::Missing) => Ok(Value::Null),
+        other => other,
     }
-    if expr.eq_ignore_ascii_case("false") {
-        return Some(Value::Bool(false));
+}
+
+fn resolve_path(expr: &Expr, ctx: &BindingContext, dialect: Dialect) -> Result<Value, EvalError> {
+    if let Some(path) = static_path(expr) {
+        return ctx.lookup(&path).ok_or(EvalError::Missing);
     }
-    if expr.eq_ignore_ascii_case("null") {
-        return Some(Value::Null);
+    match expr {
+        Expr::Member(base, name) => {
+            let base = evaluate(base, ctx, dialect)?;
+            base.get(name).cloned().ok_or(EvalError::Missing)
+        }
+        Expr::Index(base, index) => {
+            let base = evaluate(base, ctx, dialect)?;
+            let index = evaluate(index, ctx, dialect)?;
+            index_value(&base, &index)
+        }
+        other => evaluate(other, ctx, dialect),
     }
-    if expr.starts_with('"') && expr.ends_with('"') && expr.len() >= 2 {
-        return Some(Value::String(expr.trim_matches('"').to_string()));
+}
+
+/// Flattens member/index chains with literal segments into a dotted lookup path.
+fn static_path(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Identifier(name) => Some(name.clone()),
+        Expr::Member(base, name) => Some(format!("{}.{name}", static_path(base)?)),
+        Expr::Index(base, index) => {
+            let segment = match index.as_ref() {
+                Expr::Literal(Value::Number(n)) if n.is_u64() => n.to_string(),
+                Expr::Literal(Value::String(s)) if !s.contains(['.', '[', ']']) => s.clone(),
+                _ => return None,
+            };
+            Some(format!("{}.{segment}", static_path(base)?))
+        }
+        _ => None,
     }
+}
 
-    // Bare path
-    ctx.lookup(expr.trim())
+fn index_value(base: &Value, index: &Value) -> Result<Value, EvalError> {
+    let found = match (base, index) {
+        (Value::Array(items), Value::Number(n)) => n
+            .as_u64()
+            .and_then(|i| usize::try_from(i).ok())
+            .and_then(|i| items.get(i)),
+        (Value::Object(map), Value::String(key)) => map.get(key),
+        (Value::Object(map), Value::Number(n)) => map.get(&n.to_string()),
+        _ => {
+            return Err(EvalError::Invalid(format!(
+                "cannot index {} with {}",
+                type_name(base),
+                type_name(index)
+            )));
+        }
+    };
+    found.cloned().ok_or(EvalError::Missing)
 }
 
-fn split_top_level(expr: &str, separator: char) -> Option<(&str, &str)> {
-    let mut depth: i32 = 0;
-    for (idx, ch) in expr.char_indices() {
-        match ch {
-            '(' | '{' => depth += 1,
-            ')' | '}' => depth = depth.saturating_sub(1),
-            _ => {}
+fn eval_binary(
+    op: BinaryOp,
+    left: &Expr,
+    right: &Expr,
+    ctx: &BindingContext,
+    dialect: Dialect,
+) -> Result<Value, EvalError> {
+    match op {
+        BinaryOp::Or | BinaryOp::And => {
+            let l = or_null(evaluate(left, ctx, dialect))?;
+            // `||` short-circuits on a truthy left side, `&&` on a falsy one.
+            let decided = truthy(&l) == (op == BinaryOp::Or);
+            let result = if decided {
+                l
+            } else if dialect == Dialect::Adaptive {
+                or_null(evaluate(right, ctx, dialect))?
+            } else {
+                evaluate(right, ctx, dialect)?
+            };
+            match dialect {
+                Dialect::Simple => Ok(result),
+                Dialect::Adaptive => Ok(Value::Bool(truthy(&result))),
+            }
         }
-        if depth == 0 && ch == separator {
-            return Some((&expr[..idx], &expr[idx + 1..]));
+        BinaryOp::Eq | BinaryOp::Ne => {
+            let l = or_null(evaluate(left, ctx, dialect))?;
+            let r = or_null(evaluate(right, ctx, dialect))?;
+            Ok(Value::Bool(equals(&l, &r) == (op == BinaryOp::Eq)))
         }
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let l = evaluate(left, ctx, dialect)?;
+            let r = evaluate(right, ctx, dialect)?;
+            let ordering = compare(&l, &r).ok_or_else(|| {
+                EvalError::Invalid(format!(
+                    "cannot compare {} with {}",
+                    type_name(&l),
+                    type_name(&r)
+                ))
+            })?;
+            Ok(Value::Bool(match op {
+                BinaryOp::Lt => ordering == Ordering::Less,
+                BinaryOp::Le => ordering != Ordering::Greater,
+                BinaryOp::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }))
+        }
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+            let l = evaluate(left, ctx, dialect)?;
+            let r = evaluate(right, ctx, dialect)?;
+            arithmetic(op, &l, &r, dialect)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Num {
+    Int(i64),
+    Float(f64),
+}
+
+pub(crate) fn as_number(value: &Value) -> Option<Num> {
+    let Value::Number(n) = value else {
+        return None;
+    };
+    n.as_i64()
+        .map(Num::Int)
+        .or_else(|| n.as_f64().map(Num::Float))
+}
+
+/// Converts a float result back to JSON, folding integral values into integers so
+/// `1.5 * 2` renders as `3` rather than `3.0`.
+pub(crate) fn float_value(f: f64) -> Value {
+    if f.fract() == 0.0 && f.abs() < 9_007_199_254_740_992.0 {
+        return Value::from(f as i64);
     }
-    None
+    Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
 }
 
-fn split_equality(expr: &str) -> Option<(&str, &str)> {
-    let mut depth: i32 = 0;
-    let bytes = expr.as_bytes();
-    let mut idx = 0;
-    while idx + 1 < bytes.len() {
-        match bytes[idx] as char {
-            '(' | '{' => depth += 1,
-            ')' | '}' => depth = depth.saturating_sub(1),
-            '=' if depth == 0 && bytes[idx + 1] == b'=' => {
-                return Some((&expr[..idx], &expr[idx + 2..]));
+fn arithmetic(op: BinaryOp, l: &Value, r: &Value, dialect: Dialect) -> Result<Value, EvalError> {
+    if op == BinaryOp::Add && (l.is_string() || r.is_string()) {
+        return Ok(Value::String(format!(
+            "{}{}",
+            stringify_value(l),
+            stringify_value(r)
+        )));
+    }
+    let (Some(a), Some(b)) = (as_number(l), as_number(r)) else {
+        return Err(EvalError::Invalid(format!(
+            "arithmetic requires numbers, found {} and {}",
+            type_name(l),
+            type_name(r)
+        )));
+    };
+    if matches!(op, BinaryOp::Div | BinaryOp::Rem) && is_zero(b) {
+        return Err(EvalError::Invalid("division by zero".into()));
+    }
+    if let (Num::Int(x), Num::Int(y)) = (a, b) {
+        let exact = match op {
+            BinaryOp::Add => x.checked_add(y),
+            BinaryOp::Sub => x.checked_sub(y),
+            BinaryOp::Mul => x.checked_mul(y),
+            BinaryOp::Div if dialect == Dialect::Adaptive || x.checked_rem(y) == Some(0) => {
+                x.checked_div(y)
             }
-            _ => {}
+            BinaryOp::Rem => x.checked_rem(y),
+            _ => None,
+        };
+        if let Some(result) = exact {
+            return Ok(Value::from(result));
+        }
+    }
+    let (x, y) = (to_f64(a), to_f64(b));
+    let result = match op {
+        BinaryOp::Add => x + y,
+        BinaryOp::Sub => x - y,
+        BinaryOp::Mul => x * y,
+        BinaryOp::Div => x / y,
+        _ => x % y,
+    };
+    Ok(float_value(result))
+}
+
+pub(crate) fn is_zero(n: Num) -> bool {
+    match n {
+        Num::Int(i) => i == 0,
+        Num::Float(f) => f == 0.0,
+    }
+}
+
+pub(crate) fn to_f64(n: Num) -> f64 {
+    match n {
+        Num::Int(i) => i as f64,
+        Num::Float(f) => f,
+    }
+}
+
+pub(crate) fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(_), Value::Number(_)) => {
+            to_f64(as_number(a)?).partial_cmp(&to_f64(as_number(b)?))
         }
-        idx += 1;
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+pub(crate) fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
     }
-    None
 }
 
 pub(crate) fn truthy(v: &Value) -> bool {
@@ -139,3 +588,206 @@ pub(crate) fn stringify_value(v: &Value) -> String {
         other => other.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::AdaptiveCardInvocation;
+    use serde_json::json;
+
+    fn ctx(payload: Value) -> BindingContext {
+        BindingContext::from_invocation(&AdaptiveCardInvocation {
+            payload,
+            ..Default::default()
+        })
+    }
+
+    fn eval(expr: &str, payload: Value) -> Result<Option<Value>, ComponentError> {
+        SimpleExpressionEngine.try_eval(expr, &ctx(payload))
+    }
+
+    #[test]
+    fn respects_operator_precedence() {
+        let payload = json!({ "a": 2, "b": 3 });
+        assert_eq!(eval("1 + a * b", payload.clone()).unwrap(), Some(json!(7)));
+        assert_eq!(
+            eval("(1 + a) * b", payload.clone()).unwrap(),
+            Some(json!(9))
+        );
+        assert_eq!(eval("7 % 4 - 1", payload.clone()).unwrap(), Some(json!(2)));
+        assert_eq!(eval("7 / 2", payload.clone()).unwrap(), Some(json!(3.5)));
+        assert_eq!(
+            eval("a < b && !(a >= b) || false", payload.clone()).unwrap(),
+            Some(json!(true))
+        );
+        assert_eq!(eval("a != 2", payload).unwrap(), Some(json!(false)));
+    }
+
+    #[test]
+    fn ternaries_nest_and_keep_quoted_separators() {
+        let payload = json!({ "status": "ok", "level": 2 });
+        assert_eq!(
+            eval("status == \"ok\" ? \"x:y\" : \"z\"", payload.clone()).unwrap(),
+            Some(json!("x:y"))
+        );
+        assert_eq!(
+            eval(
+                "level == 1 ? 'one' : level == 2 ? 'two' : 'many'",
+                payload.clone()
+            )
+            .unwrap(),
+            Some(json!("two"))
+        );
+        assert_eq!(
+            eval("'it\\'s ' + status + \"\\u0021\"", payload).unwrap(),
+            Some(json!("it's ok!"))
+        );
+    }
+
+    #[test]
+    fn resolves_member_and_index_access() {
+        let payload = json!({ "items": [ { "name": "Pen" }, { "name": "Desk" } ], "i": 1 });
+        assert_eq!(
+            eval("payload.items[0].name", payload.clone()).unwrap(),
+            Some(json!("Pen"))
+        );
+        assert_eq!(
+            eval("payload.items[i].name", payload.clone()).unwrap(),
+            Some(json!("Desk"))
+        );
+        assert_eq!(
+            eval("payload.items[5].name", payload.clone()).unwrap(),
+            None
+        );
+        assert_eq!(
+            eval("payload.missing == null", payload).unwrap(),
+            Some(json!(true))
+        );
+    }
+
+    #[test]
+    fn parse_errors_report_column() {
+        let err = eval("a == == b", json!({})).unwrap_err();
+        assert!(
+            matches!(&err, ComponentError::Binding(msg) if msg.contains("column 6")),
+            "unexpected error: {err}"
+        );
+        let err = eval("(a + 1", json!({ "a": 1 })).unwrap_err();
+        assert!(
+            matches!(&err, ComponentError::Binding(msg) if msg.contains("column 7")),
+            "unexpected error: {err}"
+        );
+        let err = eval("\"open", json!({})).unwrap_err();
+        assert!(matches!(&err, ComponentError::Binding(msg) if msg.contains("unterminated")));
+    }
+
+    #[test]
+    fn calls_builtin_functions() {
+        let payload = json!({ "name": "ada lovelace", "scores": [3, 9, 4], "tags": ["a", "b"] });
+        let cases = [
+            ("toUpper(name)", json!("ADA LOVELACE")),
+            ("titleCase(name)", json!("Ada Lovelace")),
+            ("substring(name, 4, 8)", json!("lovelace")),
+            ("concat(name, '!', count(tags))", json!("ada lovelace!2")),
+            ("join(tags, ', ', ' and ')", json!("a and b")),
+            ("max(scores) - min(scores)", json!(6)),
+            ("average(scores)", json!(5.333333333333333)),
+            ("div(7, 2)", json!(3)),
+            ("round(2.345, 2)", json!(2.35)),
+            ("first(sortByDescending(scores))", json!(9)),
+            ("contains(tags, 'b') && !empty(scores)", json!(true)),
+            ("if(exists(missing), 'yes', 'no')", json!("no")),
+            ("coalesce(missing, nickname, 'anon')", json!("anon")),
+            ("int('42') + float('0.5')", json!(42.5)),
+            ("addOrdinal(22)", json!("22nd")),
+            ("replaceIgnoreCase('Ⱥab Ω', 'B', 'x')", json!("Ⱥax Ω")),
+            ("formatNumber(1234567.891, 2)", json!("1,234,567.89")),
+            ("base64ToString(base64(name))", json!("ada lovelace")),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                eval(expr, payload.clone()).unwrap(),
+                Some(expected),
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn date_functions_use_utc_iso_output() {
+        let payload = json!({ "when": "2024-01-31T22:30:00+02:00" });
+        let cases = [
+            ("addDays(when, 1)", json!("2024-02-01T20:30:00.000Z")),
+            (
+                "addToTime(when, 1, 'month')",
+                json!("2024-02-29T20:30:00.000Z"),
+            ),
+            (
+                "formatDateTime(when, 'dddd d MMM yyyy HH:mm')",
+                json!("Wednesday 31 Jan 2024 22:30"),
+            ),
+            ("formatDateTime(when)", json!("2024-01-31T20:30:00.000Z")),
+            ("dayOfWeek(when)", json!(3)),
+            ("startOfMonth(when, 'yyyy-MM-dd')", json!("2024-01-01")),
+            ("isDateTime('2024-02-30')", json!(false)),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(
+                eval(expr, payload.clone()).unwrap(),
+                Some(expected),
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_functions_and_bad_arguments_are_errors() {
+        let err = eval("shout(name)", json!({ "name": "x" })).unwrap_err();
+        assert!(
+            matches!(&err, ComponentError::Binding(msg) if msg.contains("unknown function `shout`"))
+        );
+        for expr in [
+            "substring('abc', 2, 5)",
+            "substring('abc', 9223372036854775807, 9223372036854775807)",
+        ] {
+            let err = eval(expr, json!({})).unwrap_err();
+            assert!(
+                matches!(&err, ComponentError::Binding(msg) if msg.starts_with("substring():")),
+                "{expr}: {err}"
+            );
+        }
+        for expr in [
+            "div(-9223372036854775807 - 1, -1)",
+            "mod(-9223372036854775807 - 1, -1)",
+            "range(9223372036854775807, 2)",
+            "addDays('2024-01-01', 9223372036854775807)",
+            "addToTime('2024-01-01', -9223372036854775807 - 1, 'weeks')",
+            "subtractFromTime('2024-01-01', 10000, 'years')",
+        ] {
+            let err = eval(expr, json!({})).unwrap_err();
+            assert!(
+                matches!(&err, ComponentError::Binding(msg) if msg.contains("overflow") || msg.contains("out of range")),
+                "{expr}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn adaptive_dialect_differs_from_simple() {
+        let payload = json!({ "name": "Ada" });
+        let adaptive = |expr: &str| AdaptiveExpressionEngine.try_eval(expr, &ctx(payload.clone()));
+        assert_eq!(
+            eval("nickname || name", payload.clone()).unwrap(),
+            Some(json!("Ada"))
+        );
+        assert_eq!(adaptive("nickname || name").unwrap(), Some(json!(true)));
+        assert_eq!(eval("7 / 2", payload.clone()).unwrap(), Some(json!(3.5)));
+        assert_eq!(adaptive("7 / 2").unwrap(), Some(json!(3)));
+        assert_eq!(eval("length(nickname)", payload.clone()).unwrap(), None);
+        assert!(adaptive("length(nickname)").is_err());
+        assert_eq!(
+            adaptive("concat(name, nickname)").unwrap(),
+            Some(json!("Ada"))
+        );
+    }
+}
@@ -0,0 +1,1014 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+
+use crate::datetime::{self, DateTime, ISO_FORMAT, LOCAL_ISO_FORMAT};
+use crate::expression::{
+    Num, as_number, compare, equals, float_value, is_zero, stringify_value, to_f64, truthy,
+    type_name,
+};
+use crate::locale::{self, Locale};
+
+/// Host-provided expression function, called with already evaluated arguments.
+pub type HostFunction = dyn Fn(&[Value]) -> Result<Value, String> + Send + Sync;
+
+static HOST_FUNCTIONS: OnceCell<RwLock<BTreeMap<String, Arc<HostFunction>>>> = OnceCell::new();
+
+fn host_functions() -> &'static RwLock<BTreeMap<String, Arc<HostFunction>>> {
+    HOST_FUNCTIONS.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+/// Functions the evaluator handles itself, before host functions are consulted.
+const RESERVED_FUNCTIONS: &[&str] = &["if", "and", "or", "coalesce", "exists", "t"];
+
+/// Registers a named function callable from `${...}` expressions, e.g. `maskEmail(x)`.
+/// Host functions take precedence over built-ins of the same name, except `if`, `and`,
+/// `or`, `coalesce`, `exists` and `t`, which cannot be registered. Registering a name
+/// again replaces it.
+pub fn register_host_function(name: &str, function: Box<HostFunction>) -> Result<(), &'static str> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if !valid {
+        return Err("invalid function name");
+    }
+    if RESERVED_FUNCTIONS.contains(&name) {
+        return Err("reserved function name");
+    }
+    host_functions()
+        .write()
+        .map_err(|_| "host functions poisoned")?
+        .insert(name.to_string(), Arc::from(function));
+    Ok(())
+}
+
+/// Calls a host-registered or built-in function; `None` when no function has that name.
+pub(crate) fn call_function(
+    name: &str,
+    args: &[Value],
+    locale: &Locale,
+) -> Option<Result<Value, String>> {
+    // Clone the handle out of the lock so host functions may register others.
+    let host = host_functions()
+        .read()
+        .ok()
+        .and_then(|guard| guard.get(name).cloned());
+    match host {
+        Some(function) => Some(function(args)),
+        None => call_builtin(name, args, locale),
+    }
+}
+
+/// Built-in Adaptive Expressions functions. Returns `None` when `name` is not a built-in so
+/// callers can fall back to other function sources.
+///
+/// `if`, `and`, `or`, `coalesce` and `exists` need lazy or null-tolerant arguments and are
+/// evaluated by the engine itself. Formatting functions default to the invocation's
+/// `locale` and time zone.
+fn call_builtin(name: &str, args: &[Value], locale: &Locale) -> Option<Result<Value, String>> {
+    let result = match name {
+        // String
+        "concat" => concat(args),
+        "length" => arity(args, 1, 1).and_then(|_| match &args[0] {
+            Value::String(s) => Ok(Value::from(s.chars().count())),
+            Value::Array(items) => Ok(Value::from(items.len())),
+            other => Err(expected("a string or array", other)),
+        }),
+        "replace" => replace(args, false),
+        "replaceIgnoreCase" => replace(args, true),
+        "split" => arity(args, 1, 2).and_then(|_| {
+            let text = string_arg(args, 0)?;
+            let separator = optional_string(args, 1)?.unwrap_or("");
+            let parts: Vec<Value> = if separator.is_empty() {
+                text.chars().map(|c| Value::String(c.to_string())).collect()
+            } else {
+                text.split(separator)
+                    .map(|p| Value::String(p.to_string()))
+                    .collect()
+            };
+            Ok(Value::Array(parts))
+        }),
+        "substring" => arity(args, 2, 3).and_then(|_| {
+            let chars: Vec<char> = string_arg(args, 0)?.chars().collect();
+            let start = index_arg(args, 1)?;
+            let len = match args.get(2) {
+                Some(_) => index_arg(args, 2)?,
+                None => chars.len().saturating_sub(start),
+            };
+            let end = start.checked_add(len).ok_or_else(overflow)?;
+            if end > chars.len() {
+                return Err(format!(
+                    "range {start}..{end} is out of bounds for length {}",
+                    chars.len()
+                ));
+            }
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }),
+        "toLower" => map_string(args, |s| s.to_lowercase()),
+        "toUpper" => map_string(args, |s| s.to_uppercase()),
+        "trim" => map_string(args, |s| s.trim().to_string()),
+        "sentenceCase" => map_string(args, |s| {
+            let lower = s.to_lowercase();
+            let mut chars = lower.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        }),
+        "titleCase" => map_string(args, |s| {
+            s.split(' ')
+                .map(|word| {
+                    let lower = word.to_lowercase();
+                    let mut chars = lower.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }),
+        "startsWith" => arity(args, 2, 2).and_then(|_| {
+            Ok(Value::Bool(
+                string_arg(args, 0)?.starts_with(string_arg(args, 1)?),
+            ))
+        }),
+        "endsWith" => arity(args, 2, 2).and_then(|_| {
+            Ok(Value::Bool(
+                string_arg(args, 0)?.ends_with(string_arg(args, 1)?),
+            ))
+        }),
+        "addOrdinal" => arity(args, 1, 1).and_then(|_| {
+            let n = int_arg(args, 0)?;
+            let suffix = match (n % 100, n % 10) {
+                (11..=13, _) => "th",
+                (_, 1) => "st",
+                (_, 2) => "nd",
+                (_, 3) => "rd",
+                _ => "th",
+            };
+            Ok(Value::String(format!("{n}{suffix}")))
+        }),
+
+        // Math
+        "add" => fold_numbers(args, |a, b| a + b),
+        "sub" => arity(args, 2, 2).and_then(|_| binary_number(args, |a, b| a - b)),
+        "mul" => fold_numbers(args, |a, b| a * b),
+        "div" => arity(args, 2, 2).and_then(|_| {
+            match (number_arg(args, 0)?, number_arg(args, 1)?) {
+                (_, b) if is_zero(b) => Err("division by zero".to_string()),
+                // Integer operands use integer division, as in Adaptive Expressions.
+                (Num::Int(a), Num::Int(b)) => {
+                    a.checked_div(b).map(Value::from).ok_or_else(overflow)
+                }
+                (a, b) => Ok(float_value(to_f64(a) / to_f64(b))),
+            }
+        }),
+        "mod" => {
+            arity(args, 2, 2).and_then(|_| match (number_arg(args, 0)?, number_arg(args, 1)?) {
+                (_, b) if is_zero(b) => Err("division by zero".to_string()),
+                (Num::Int(a), Num::Int(b)) => {
+                    a.checked_rem(b).map(Value::from).ok_or_else(overflow)
+                }
+                (a, b) => Ok(float_value(to_f64(a) % to_f64(b))),
+            })
+        }
+        "min" => extreme(args, Ordering::Less),
+        "max" => extreme(args, Ordering::Greater),
+        "sum" => numbers_of(args).map(|nums| float_value(nums.iter().sum())),
+        "average" => numbers_of(args).and_then(|nums| {
+            if nums.is_empty() {
+                return Err("requires at least one number".to_string());
+            }
+            Ok(float_value(nums.iter().sum::<f64>() / nums.len() as f64))
+        }),
+        "abs" => unary_number(args, f64::abs),
+        "floor" => unary_number(args, f64::floor),
+        "ceiling" => unary_number(args, f64::ceil),
+        "sqrt" => unary_number(args, f64::sqrt),
+        "round" => arity(args, 1, 2).and_then(|_| {
+            let value = to_f64(number_arg(args, 0)?);
+            let digits = match args.get(1) {
+                Some(_) => int_arg(args, 1)?.clamp(0, 15) as i32,
+                None => 0,
+            };
+            let factor = 10f64.powi(digits);
+            Ok(float_value((value * factor).round() / factor))
+        }),
+        "exp" => arity(args, 2, 2).and_then(|_| binary_number(args, f64::powf)),
+        "range" => arity(args, 2, 2).and_then(|_| {
+            let start = int_arg(args, 0)?;
+            let count = int_arg(args, 1)?;
+            if !(0..=10_000).contains(&count) {
+                return Err("count must be between 0 and 10000".to_string());
+            }
+            let end = start.checked_add(count).ok_or_else(overflow)?;
+            Ok(Value::Array((start..end).map(Value::from).collect()))
+        }),
+
+        // Collection
+        "count" => arity(args, 1, 1).and_then(|_| match &args[0] {
+            Value::String(s) => Ok(Value::from(s.chars().count())),
+            Value::Array(items) => Ok(Value::from(items.len())),
+            Value::Object(map) => Ok(Value::from(map.len())),
+            other => Err(expected("a string, array or object", other)),
+        }),
+        "first" => arity(args, 1, 1).map(|_| match &args[0] {
+            Value::String(s) => s
+                .chars()
+                .next()
+                .map(|c| Value::String(c.to_string()))
+                .unwrap_or(Value::Null),
+            Value::Array(items) => items.first().cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        }),
+        "last" => arity(args, 1, 1).map(|_| match &args[0] {
+            Value::String(s) => s
+                .chars()
+                .last()
+                .map(|c| Value::String(c.to_string()))
+                .unwrap_or(Value::Null),
+            Value::Array(items) => items.last().cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        }),
+        "join" => arity(args, 2, 3).and_then(|_| {
+            let items: Vec<String> = array_arg(args, 0)?.iter().map(stringify_value).collect();
+            let separator = string_arg(args, 1)?;
+            let Some(last_separator) = optional_string(args, 2)? else {
+                return Ok(Value::String(items.join(separator)));
+            };
+            Ok(Value::String(match items.split_last() {
+                Some((last, rest)) if !rest.is_empty() => {
+                    format!("{}{last_separator}{last}", rest.join(separator))
+                }
+                _ => items.join(separator),
+            }))
+        }),
+        "contains" => arity(args, 2, 2).and_then(|_| match (&args[0], &args[1]) {
+            (Value::String(s), needle) => Ok(Value::Bool(s.contains(&stringify_value(needle)))),
+            (Value::Array(items), needle) => {
+                Ok(Value::Bool(items.iter().any(|item| equals(item, needle))))
+            }
+            (Value::Object(map), Value::String(key)) => Ok(Value::Bool(map.contains_key(key))),
+            (Value::Null, _) => Ok(Value::Bool(false)),
+            (other, _) => Err(expected("a string, array or object", other)),
+        }),
+        "indexOf" => arity(args, 2, 2).and_then(|_| position(args, false)),
+        "lastIndexOf" => arity(args, 2, 2).and_then(|_| position(args, true)),
+        "empty" => arity(args, 1, 1).map(|_| {
+            Value::Bool(match &args[0] {
+                Value::Null => true,
+                Value::String(s) => s.is_empty(),
+                Value::Array(items) => items.is_empty(),
+                Value::Object(map) => map.is_empty(),
+                _ => false,
+            })
+        }),
+        "union" => arrays_of(args).map(|arrays| {
+            let mut out: Vec<Value> = Vec::new();
+            for item in arrays.into_iter().flatten() {
+                if !out.iter().any(|existing| equals(existing, item)) {
+                    out.push(item.clone());
+                }
+            }
+            Value::Array(out)
+        }),
+        "intersection" => arrays_of(args).map(|arrays| {
+            let mut iter = arrays.into_iter();
+            let mut out: Vec<Value> = iter.next().cloned().unwrap_or_default();
+            for other in iter {
+                out.retain(|item| other.iter().any(|candidate| equals(item, candidate)));
+            }
+            out.dedup_by(|a, b| equals(a, b));
+            Value::Array(out)
+        }),
+        "unique" => arity(args, 1, 1).and_then(|_| {
+            let mut out: Vec<Value> = Vec::new();
+            for item in array_arg(args, 0)? {
+                if !out.iter().any(|existing| equals(existing, item)) {
+                    out.push(item.clone());
+                }
+            }
+            Ok(Value::Array(out))
+        }),
+        "skip" => arity(args, 2, 2).and_then(|_| {
+            let items = array_arg(args, 0)?;
+            let n = index_arg(args, 1)?.min(items.len());
+            Ok(Value::Array(items[n..].to_vec()))
+        }),
+        "take" => arity(args, 2, 2).and_then(|_| {
+            let n = index_arg(args, 1)?;
+            match &args[0] {
+                Value::String(s) => Ok(Value::String(s.chars().take(n).collect())),
+                Value::Array(items) => Ok(Value::Array(items.iter().take(n).cloned().collect())),
+                other => Err(expected("a string or array", other)),
+            }
+        }),
+        "subArray" => arity(args, 2, 3).and_then(|_| {
+            let items = array_arg(args, 0)?;
+            let start = index_arg(args, 1)?;
+            let end = match args.get(2) {
+                Some(_) => index_arg(args, 2)?,
+                None => items.len(),
+            };
+            if start > end || end > items.len() {
+                return Err(format!(
+                    "range {start}..{end} is out of bounds for length {}",
+                    items.len()
+                ));
+            }
+            Ok(Value::Array(items[start..end].to_vec()))
+        }),
+        "reverse" => arity(args, 1, 1).and_then(|_| match &args[0] {
+            Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+            Value::Array(items) => Ok(Value::Array(items.iter().rev().cloned().collect())),
+            other => Err(expected("a string or array", other)),
+        }),
+        "sortBy" => sort_by(args, false),
+        "sortByDescending" => sort_by(args, true),
+        "flatten" => arity(args, 1, 2).and_then(|_| {
+            let depth = match args.get(1) {
+                Some(_) => index_arg(args, 1)?,
+                None => 100,
+            };
+            let mut out = Vec::new();
+            flatten_into(array_arg(args, 0)?, depth, &mut out);
+            Ok(Value::Array(out))
+        }),
+        "createArray" => Ok(Value::Array(args.to_vec())),
+
+        // Logical and comparison
+        "not" => arity(args, 1, 1).map(|_| Value::Bool(!truthy(&args[0]))),
+        "equals" => arity(args, 2, 2).map(|_| Value::Bool(equals(&args[0], &args[1]))),
+        "greater" => comparison(args, |o| o == Ordering::Greater),
+        "greaterOrEquals" => comparison(args, |o| o != Ordering::Less),
+        "less" => comparison(args, |o| o == Ordering::Less),
+        "lessOrEquals" => comparison(args, |o| o != Ordering::Greater),
+
+        // Type checks
+        "isString" => type_check(args, Value::is_string),
+        "isInteger" => type_check(args, |v| v.is_i64() || v.is_u64()),
+        "isFloat" => type_check(args, Value::is_f64),
+        "isArray" => type_check(args, Value::is_array),
+        "isObject" => type_check(args, Value::is_object),
+        "isBoolean" => type_check(args, Value::is_boolean),
+        "isDateTime" => type_check(args, |v| v.as_str().and_then(datetime::parse).is_some()),
+
+        // Conversion
+        "string" => arity(args, 1, 1).map(|_| Value::String(stringify_value(&args[0]))),
+        "int" => arity(args, 1, 1).and_then(|_| match &args[0] {
+            Value::Number(_) => Ok(Value::from(to_f64(number_arg(args, 0)?).trunc() as i64)),
+            Value::String(s) => s
+                .trim()
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("cannot convert \"{s}\" to an integer")),
+            Value::Bool(b) => Ok(Value::from(i64::from(*b))),
+            other => Err(expected("a number or numeric string", other)),
+        }),
+        "float" => arity(args, 1, 1).and_then(|_| match &args[0] {
+            Value::Number(_) => Ok(args[0].clone()),
+            Value::String(s) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("cannot convert \"{s}\" to a number")),
+            other => Err(expected("a number or numeric string", other)),
+        }),
+        "bool" => arity(args, 1, 1).map(|_| {
+            Value::Bool(match &args[0] {
+                Value::String(s) if s.eq_ignore_ascii_case("false") => false,
+                Value::String(s) if s.eq_ignore_ascii_case("true") => true,
+                other => truthy(other),
+            })
+        }),
+        "json" => arity(args, 1, 1).and_then(|_| match &args[0] {
+            Value::String(s) => {
+                serde_json::from_str(s).map_err(|err| format!("invalid JSON: {err}"))
+            }
+            other => Ok(other.clone()),
+        }),
+        "jsonStringify" => arity(args, 1, 1).map(|_| Value::String(args[0].to_string())),
+        "array" => arity(args, 1, 1).map(|_| match &args[0] {
+            Value::Array(_) => args[0].clone(),
+            other => Value::Array(vec![other.clone()]),
+        }),
+        "base64" => map_string(args, |s| base64_encode(s.as_bytes())),
+        "base64ToString" => arity(args, 1, 1).and_then(|_| {
+            let decoded = base64_decode(string_arg(args, 0)?)
+                .ok_or_else(|| "invalid base64 input".to_string())?;
+            String::from_utf8(decoded)
+                .map(Value::String)
+                .map_err(|_| "decoded bytes are not UTF-8".to_string())
+        }),
+        "uriComponent" => map_string(args, uri_encode),
+        "uriComponentToString" => arity(args, 1, 1).and_then(|_| {
+            uri_decode(string_arg(args, 0)?)
+                .map(Value::String)
+                .ok_or_else(|| "invalid percent-encoding".to_string())
+        }),
+        "formatNumber" => arity(args, 2, 3).and_then(|_| {
+            let value = to_f64(number_arg(args, 0)?);
+            let precision = index_arg(args, 1)?.min(15);
+            Ok(Value::String(
+                locale_arg(args, 2, locale)?.format_number(value, precision),
+            ))
+        }),
+        "formatPercent" => arity(args, 1, 3).and_then(|_| {
+            let value = to_f64(number_arg(args, 0)?);
+            let precision = optional_index(args, 1)?.unwrap_or(0).min(15);
+            Ok(Value::String(
+                locale_arg(args, 2, locale)?.format_percent(value, precision),
+            ))
+        }),
+        "formatCurrency" => arity(args, 2, 4).and_then(|_| {
+            let value = to_f64(number_arg(args, 0)?);
+            let code = string_arg(args, 1)?;
+            let precision = optional_index(args, 2)?.map(|p| p.min(15));
+            Ok(Value::String(
+                locale_arg(args, 3, locale)?.format_currency(value, code, precision),
+            ))
+        }),
+
+        // Date and time
+        "utcNow" => arity(args, 0, 1).and_then(|_| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or_default();
+            format_timestamp(
+                DateTime {
+                    millis: now,
+                    offset_minutes: 0,
+                },
+                args,
+                0,
+            )
+        }),
+        "formatDateTime" => arity(args, 1, 3).and_then(|_| {
+            let dt = timestamp_arg(args, 0)?;
+            let pattern = optional_string(args, 1)?.unwrap_or(ISO_FORMAT);
+            let dt = if pattern == ISO_FORMAT {
+                dt.with_offset(0)
+            } else {
+                dt
+            };
+            Ok(Value::String(
+                locale_arg(args, 2, locale)?.format_pattern(&dt, pattern),
+            ))
+        }),
+        "formatDate" => arity(args, 1, 3).and_then(|_| {
+            let dt = timestamp_arg(args, 0)?;
+            let style = optional_string(args, 1)?.unwrap_or("yyyy-MM-dd");
+            locale_arg(args, 2, locale)?
+                .format_date(dt, style)
+                .map(Value::String)
+        }),
+        "formatTime" => arity(args, 1, 3).and_then(|_| {
+            let dt = timestamp_arg(args, 0)?;
+            let style = optional_string(args, 1)?.unwrap_or("short");
+            locale_arg(args, 2, locale)?
+                .format_time(dt, style)
+                .map(Value::String)
+        }),
+        "formatDuration" => arity(args, 1, 3).and_then(|_| {
+            let millis = locale::parse_duration(&args[0]).ok_or_else(|| {
+                "argument 1 must be milliseconds or an ISO 8601 duration".to_string()
+            })?;
+            let short = match optional_string(args, 1)?.unwrap_or("long") {
+                "long" => false,
+                "short" => true,
+                other => return Err(format!("unknown duration style \"{other}\"")),
+            };
+            Ok(Value::String(
+                locale_arg(args, 2, locale)?.format_duration(millis, short),
+            ))
+        }),
+        "convertFromUTC" => arity(args, 2, 3).and_then(|_| {
+            let dt = timestamp_arg(args, 0)?;
+            let zone = locale::time_zone(string_arg(args, 1)?)?;
+            let pattern = optional_string(args, 2)?.unwrap_or(LOCAL_ISO_FORMAT);
+            Ok(Value::String(datetime::format(&zone.localize(dt), pattern)))
+        }),
+        "convertToUTC" => arity(args, 2, 3).and_then(|_| {
+            let dt = timestamp_arg(args, 0)?;
+            let zone = locale::time_zone(string_arg(args, 1)?)?;
+            format_timestamp(zone.resolve_wall_clock(dt), args, 2)
+        }),
+        "addDays" => add_interval(args, 86_400_000),
+        "addHours" => add_interval(args, 3_600_000),
+        "addMinutes" => add_interval(args, 60_000),
+        "addSeconds" => add_interval(args, 1000),
+        "addToTime" => add_to_time(args, 1),
+        "subtractFromTime" => add_to_time(args, -1),
+        "startOfDay" => arity(args, 1, 2).and_then(|_| {
+            let f = timestamp_arg(args, 0)?.with_offset(0).fields();
+            format_timestamp(
+                DateTime::from_local((f.year, f.month, f.day), (0, 0, 0, 0), 0),
+                args,
+                1,
+            )
+        }),
+        "startOfMonth" => arity(args, 1, 2).and_then(|_| {
+            let f = timestamp_arg(args, 0)?.with_offset(0).fields();
+            format_timestamp(
+                DateTime::from_local((f.year, f.month, 1), (0, 0, 0, 0), 0),
+                args,
+                1,
+            )
+        }),
+        "dayOfMonth" => date_part(args, |dt| i64::from(dt.fields().day)),
+        "dayOfWeek" => date_part(args, |dt| i64::from(dt.fields().weekday)),
+        "dayOfYear" => date_part(args, |dt| i64::from(dt.day_of_year())),
+        "month" => date_part(args, |dt| i64::from(dt.fields().month)),
+        "year" => date_part(args, |dt| dt.fields().year),
+        "ticks" => date_part(args, |dt| dt.millis * 10_000 + 621_355_968_000_000_000),
+        "date" => arity(args, 1, 1).and_then(|_| {
+            let dt = timestamp_arg(args, 0)?.with_offset(0);
+            Ok(Value::String(datetime::format(&dt, "M/d/yyyy")))
+        }),
+        "getTimeOfDay" => arity(args, 1, 1).and_then(|_| {
+            let f = timestamp_arg(args, 0)?.with_offset(0).fields();
+            let minutes = f.hour * 60 + f.minute;
+            let label = match minutes {
+                0 => "midnight",
+                720 => "noon",
+                1..=719 => "morning",
+                721..=1079 => "afternoon",
+                1080..=1319 => "evening",
+                _ => "night",
+            };
+            Ok(Value::String(label.to_string()))
+        }),
+        "dateTimeDiff" => arity(args, 2, 2).and_then(|_| {
+            let a = timestamp_arg(args, 0)?;
+            let b = timestamp_arg(args, 1)?;
+            Ok(Value::from((a.millis - b.millis) * 10_000))
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn expected(what: &str, found: &Value) -> String {
+    format!("expected {what}, found {}", type_name(found))
+}
+
+fn arity(args: &[Value], min: usize, max: usize) -> Result<(), String> {
+    if (min..=max).contains(&args.len()) {
+        return Ok(());
+    }
+    let wanted = if min == max {
+        min.to_string()
+    } else {
+        format!("{min} to {max}")
+    };
+    Err(format!(
+        "expects {wanted} argument(s) but got {}",
+        args.len()
+    ))
+}
+
+fn string_arg(args: &[Value], idx: usize) -> Result<&str, String> {
+    match args.get(idx) {
+        Some(Value::String(s)) => Ok(s),
+        Some(other) => Err(format!(
+            "argument {} {}",
+            idx + 1,
+            expected("a string", other)
+        )),
+        None => Err(format!("missing argument {}", idx + 1)),
+    }
+}
+
+fn optional_string(args: &[Value], idx: usize) -> Result<Option<&str>, String> {
+    match args.get(idx) {
+        None | Some(Value::Null) => Ok(None),
+        Some(_) => string_arg(args, idx).map(Some),
+    }
+}
+
+fn number_arg(args: &[Value], idx: usize) -> Result<Num, String> {
+    match args.get(idx) {
+        Some(value) => as_number(value)
+            .ok_or_else(|| format!("argument {} {}", idx + 1, expected("a number", value))),
+        None => Err(format!("missing argument {}", idx + 1)),
+    }
+}
+
+fn int_arg(args: &[Value], idx: usize) -> Result<i64, String> {
+    match number_arg(args, idx)? {
+        Num::Int(i) => Ok(i),
+        Num::Float(f) if f.fract() == 0.0 => Ok(f as i64),
+        Num::Float(_) => Err(format!("argument {} must be an integer", idx + 1)),
+    }
+}
+
+fn index_arg(args: &[Value], idx: usize) -> Result<usize, String> {
+    usize::try_from(int_arg(args, idx)?)
+        .map_err(|_| format!("argument {} must not be negative", idx + 1))
+}
+
+fn array_arg(args: &[Value], idx: usize) -> Result<&Vec<Value>, String> {
+    match args.get(idx) {
+        Some(Value::Array(items)) => Ok(items),
+        Some(other) => Err(format!(
+            "argument {} {}",
+            idx + 1,
+            expected("an array", other)
+        )),
+        None => Err(format!("missing argument {}", idx + 1)),
+    }
+}
+
+fn arrays_of(args: &[Value]) -> Result<Vec<&Vec<Value>>, String> {
+    if args.is_empty() {
+        return Err("expects at least one array".to_string());
+    }
+    (0..args.len()).map(|idx| array_arg(args, idx)).collect()
+}
+
+fn optional_index(args: &[Value], idx: usize) -> Result<Option<usize>, String> {
+    match args.get(idx) {
+        None | Some(Value::Null) => Ok(None),
+        Some(_) => index_arg(args, idx).map(Some),
+    }
+}
+
+/// The invocation locale, or the locale tag passed at `idx`.
+fn locale_arg(args: &[Value], idx: usize, locale: &Locale) -> Result<Locale, String> {
+    Ok(match optional_string(args, idx)? {
+        Some(tag) => locale.with_tag(tag),
+        None => locale.clone(),
+    })
+}
+
+fn timestamp_arg(args: &[Value], idx: usize) -> Result<DateTime, String> {
+    let raw = string_arg(args, idx)?;
+    datetime::parse(raw).ok_or_else(|| format!("invalid timestamp \"{raw}\""))
+}
+
+/// Renders a computed timestamp using the optional format argument at `format_idx`,
+/// defaulting to UTC ISO 8601.
+fn format_timestamp(dt: DateTime, args: &[Value], format_idx: usize) -> Result<Value, String> {
+    let pattern = optional_string(args, format_idx)?.unwrap_or(ISO_FORMAT);
+    Ok(Value::String(datetime::format(&dt.with_offset(0), pattern)))
+}
+
+fn add_interval(args: &[Value], unit_millis: i64) -> Result<Value, String> {
+    arity(args, 2, 3)?;
+    let dt = timestamp_arg(args, 0)?;
+    let shifted = int_arg(args, 1)?
+        .checked_mul(unit_millis)
+        .and_then(|delta| dt.add_millis(delta))
+        .ok_or_else(out_of_range)?;
+    format_timestamp(shifted, args, 2)
+}
+
+fn add_to_time(args: &[Value], sign: i64) -> Result<Value, String> {
+    arity(args, 3, 4)?;
+    let dt = timestamp_arg(args, 0)?;
+    let amount = int_arg(args, 1)?
+        .checked_mul(sign)
+        .ok_or_else(out_of_range)?;
+    let millis = |unit: i64| {
+        amount
+            .checked_mul(unit)
+            .and_then(|delta| dt.add_millis(delta))
+    };
+    let shifted = match string_arg(args, 2)?.to_ascii_lowercase().as_str() {
+        "second" | "seconds" => millis(1000),
+        "minute" | "minutes" => millis(60_000),
+        "hour" | "hours" => millis(3_600_000),
+        "day" | "days" => millis(86_400_000),
+        "week" | "weeks" => millis(7 * 86_400_000),
+        "month" | "months" => dt.add_months(amount),
+        "year" | "years" => amount
+            .checked_mul(12)
+            .and_then(|months| dt.add_months(months)),
+        other => return Err(format!("unknown time unit \"{other}\"")),
+    };
+    format_timestamp(shifted.ok_or_else(out_of_range)?, args, 3)
+}
+
+fn overflow() -> String {
+    "integer overflow".to_string()
+}
+
+fn out_of_range() -> String {
+    "resulting timestamp is out of range".to_string()
+}
+
+fn date_part(args: &[Value], part: impl Fn(&DateTime) -> i64) -> Result<Value, String> {
+    arity(args, 1, 1)?;
+    Ok(Value::from(part(&timestamp_arg(args, 0)?.with_offset(0))))
+}
+
+fn map_string(args: &[Value], f: impl Fn(&str) -> String) -> Result<Value, String> {
+    arity(args, 1, 1)?;
+    match &args[0] {
+        Value::Null => Ok(Value::String(String::new())),
+        _ => Ok(Value::String(f(string_arg(args, 0)?))),
+    }
+}
+
+fn concat(args: &[Value]) -> Result<Value, String> {
+    if !args.is_empty() && args.iter().all(Value::is_array) {
+        let items = args
+            .iter()
+            .filter_map(Value::as_array)
+            .flatten()
+            .cloned()
+            .collect();
+        return Ok(Value::Array(items));
+    }
+    Ok(Value::String(
+        args.iter()
+            .map(|v| match v {
+                Value::Null => String::new(),
+                other => stringify_value(other),
+            })
+            .collect(),
+    ))
+}
+
+fn replace(args: &[Value], ignore_case: bool) -> Result<Value, String> {
+    arity(args, 3, 3)?;
+    let text = string_arg(args, 0)?;
+    let from = string_arg(args, 1)?;
+    let to = string_arg(args, 2)?;
+    if from.is_empty() {
+        return Err("search string must not be empty".to_string());
+    }
+    if !ignore_case {
+        return Ok(Value::String(text.replace(from, to)));
+    }
+    let lower_from = from.to_lowercase();
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match lowercase_prefix_len(rest, &lower_from) {
+            Some(len) => {
+                out.push_str(to);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    Ok(Value::String(out))
+}
+
+/// Byte length of the shortest prefix of `text` whose lowercase form is `lower`, comparing
+/// whole characters so offsets always refer to `text` itself.
+fn lowercase_prefix_len(text: &str, lower: &str) -> Option<usize> {
+    let mut wanted = lower.chars().peekable();
+    for (index, c) in text.char_indices() {
+        for folded in c.to_lowercase() {
+            if wanted.next() != Some(folded) {
+                return None;
+            }
+        }
+        if wanted.peek().is_none() {
+            return Some(index + c.len_utf8());
+        }
+    }
+    None
+}
+
+fn position(args: &[Value], last: bool) -> Result<Value, String> {
+    let found = match (&args[0], &args[1]) {
+        (Value::String(s), needle) => {
+            let needle = stringify_value(needle);
+            let byte_pos = if last {
+                s.rfind(&needle)
+            } else {
+                s.find(&needle)
+            };
+            byte_pos.map(|pos| s[..pos].chars().count())
+        }
+        (Value::Array(items), needle) => {
+            let mut positions = items.iter().enumerate().filter(|(_, v)| equals(v, needle));
+            if last {
+                positions.next_back().map(|(idx, _)| idx)
+            } else {
+                positions.next().map(|(idx, _)| idx)
+            }
+        }
+        (other, _) => return Err(expected("a string or array", other)),
+    };
+    Ok(found.map(Value::from).unwrap_or(Value::from(-1)))
+}
+
+fn numbers_of(args: &[Value]) -> Result<Vec<f64>, String> {
+    let values: &[Value] = match args {
+        [Value::Array(items)] => items,
+        other => other,
+    };
+    values
+        .iter()
+        .map(|v| {
+            as_number(v)
+                .map(to_f64)
+                .ok_or_else(|| expected("numbers", v))
+        })
+        .collect()
+}
+
+fn fold_numbers(args: &[Value], op: impl Fn(f64, f64) -> f64) -> Result<Value, String> {
+    if args.len() < 2 {
+        return Err(format!(
+            "expects at least 2 arguments but got {}",
+            args.len()
+        ));
+    }
+    let all_int = args.iter().all(|v| v.is_i64());
+    let nums = numbers_of(args)?;
+    let result = nums.into_iter().reduce(op).unwrap_or_default();
+    if all_int && result.abs() < 9_007_199_254_740_992.0 {
+        return Ok(Value::from(result as i64));
+    }
+    Ok(float_value(result))
+}
+
+fn binary_number(args: &[Value], op: impl Fn(f64, f64) -> f64) -> Result<Value, String> {
+    let a = to_f64(number_arg(args, 0)?);
+    let b = to_f64(number_arg(args, 1)?);
+    Ok(float_value(op(a, b)))
+}
+
+fn unary_number(args: &[Value], op: impl Fn(f64) -> f64) -> Result<Value, String> {
+    arity(args, 1, 1)?;
+    Ok(float_value(op(to_f64(number_arg(args, 0)?))))
+}
+
+fn extreme(args: &[Value], wanted: Ordering) -> Result<Value, String> {
+    let values: &[Value] = match args {
+        [Value::Array(items)] => items,
+        other => other,
+    };
+    let mut best: Option<&Value> = None;
+    for value in values {
+        if as_number(value).is_none() {
+            return Err(expected("numbers", value));
+        }
+        if best.is_none_or(|current| compare(value, current) == Some(wanted)) {
+            best = Some(value);
+        }
+    }
+    best.cloned()
+        .ok_or_else(|| "requires at least one number".to_string())
+}
+
+fn comparison(args: &[Value], accept: impl Fn(Ordering) -> bool) -> Result<Value, String> {
+    arity(args, 2, 2)?;
+    compare(&args[0], &args[1])
+        .map(|ordering| Value::Bool(accept(ordering)))
+        .ok_or_else(|| {
+            format!(
+                "cannot compare {} with {}",
+                type_name(&args[0]),
+                type_name(&args[1])
+            )
+        })
+}
+
+fn type_check(args: &[Value], check: impl Fn(&Value) -> bool) -> Result<Value, String> {
+    arity(args, 1, 1)?;
+    Ok(Value::Bool(check(&args[0])))
+}
+
+fn sort_by(args: &[Value], descending: bool) -> Result<Value, String> {
+    arity(args, 1, 2)?;
+    let mut items = array_arg(args, 0)?.clone();
+    let key = optional_string(args, 1)?;
+    let pick = |v: &Value| -> Value {
+        match key {
+            Some(key) => v.get(key).cloned().unwrap_or(Value::Null),
+            None => v.clone(),
+        }
+    };
+    items.sort_by(|a, b| {
+        let ordering = compare(&pick(a), &pick(b)).unwrap_or(Ordering::Equal);
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    Ok(Value::Array(items))
+}
+
+fn flatten_into(items: &[Value], depth: usize, out: &mut Vec<Value>) {
+    for item in items {
+        match item {
+            Value::Array(inner) if depth > 0 => flatten_into(inner, depth - 1, out),
+            other => out.push(other.clone()),
+        }
+    }
+}
+
+/// Formats a number with a fixed number of decimals and digit grouping.
+pub(crate) fn format_grouped(value: f64, precision: usize, group: &str, decimal: &str) -> String {
+    let fixed = format!("{:.*}", precision, value.abs());
+    let (int_part, frac_part) = fixed.split_once('.').unwrap_or((&fixed, ""));
+    let mut grouped = String::new();
+    for (idx, ch) in int_part.chars().enumerate() {
+        if idx > 0 && (int_part.len() - idx) % 3 == 0 {
+            grouped.push_str(group);
+        }
+        grouped.push(ch);
+    }
+    let negative = value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0');
+    let mut out = String::new();
+    if negative {
+        out.push('-');
+    }
+    out.push_str(&grouped);
+    if !frac_part.is_empty() {
+        out.push_str(decimal);
+        out.push_str(frac_part);
+    }
+    out
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let clean: Vec<u8> = input
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace() && *b != b'=')
+        .collect();
+    let mut out = Vec::new();
+    for chunk in clean.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, byte) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|c| c == byte)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i) & 0xff) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn uri_encode(input: &str) -> String {
+    let mut out = String::new();
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'()".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+fn uri_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = input.get(idx + 1..idx + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            idx += 3;
+        } else {
+            out.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
@@ -0,0 +1,502 @@
+use serde_json::Value;
+
+/// Parsed expression tree evaluated by the expression engines.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expr {
+    Literal(Value),
+    /// Root identifier of a path, e.g. `payload`, `$data` or a bare field name.
+    Identifier(String),
+    Member(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Function call such as `concat(a, b)`; only bare identifiers are callable.
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UnaryOp {
+    Not,
+    Negate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParseError {
+    /// 1-based character column where parsing failed.
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    Literal(Value),
+    Str(String),
+    Ident(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    Comma,
+    Question,
+    Colon,
+    Not,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    End,
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    column: usize,
+}
+
+/// Parses a complete expression, rejecting trailing input.
+pub(crate) fn parse(input: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_conditional()?;
+    let next = parser.peek();
+    if next.kind != TokenKind::End {
+        return Err(ParseError {
+            column: next.column,
+            message: format!("unexpected {}", describe(&next.kind)),
+        });
+    }
+    Ok(expr)
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let ch = chars[idx];
+        let column = idx + 1;
+        if ch.is_whitespace() {
+            idx += 1;
+            continue;
+        }
+        let two = chars.get(idx + 1).copied();
+        let (kind, len) = match (ch, two) {
+            ('=', Some('=')) => (TokenKind::Eq, 2),
+            ('!', Some('=')) => (TokenKind::Ne, 2),
+            ('<', Some('=')) => (TokenKind::Le, 2),
+            ('>', Some('=')) => (TokenKind::Ge, 2),
+            ('&', Some('&')) => (TokenKind::And, 2),
+            ('|', Some('|')) => (TokenKind::Or, 2),
+            ('!', _) => (TokenKind::Not, 1),
+            ('<', _) => (TokenKind::Lt, 1),
+            ('>', _) => (TokenKind::Gt, 1),
+            ('(', _) => (TokenKind::LParen, 1),
+            (')', _) => (TokenKind::RParen, 1),
+            ('[', _) => (TokenKind::LBracket, 1),
+            (']', _) => (TokenKind::RBracket, 1),
+            (',', _) => (TokenKind::Comma, 1),
+            ('?', _) => (TokenKind::Question, 1),
+            (':', _) => (TokenKind::Colon, 1),
+            ('+', _) => (TokenKind::Plus, 1),
+            ('-', _) => (TokenKind::Minus, 1),
+            ('*', _) => (TokenKind::Star, 1),
+            ('/', _) => (TokenKind::Slash, 1),
+            ('%', _) => (TokenKind::Percent, 1),
+            ('.', _) => (TokenKind::Dot, 1),
+            ('"' | '\'', _) => lex_string(&chars, idx)?,
+            (c, _) if c.is_ascii_digit() => {
+                // After a member dot only an integer segment is valid (`rows.0.1`).
+                let member = tokens
+                    .last()
+                    .is_some_and(|t: &Token| t.kind == TokenKind::Dot);
+                lex_number(&chars, idx, member)?
+            }
+            (c, _) if is_ident_start(c) => lex_identifier(&chars, idx)?,
+            (c, _) => {
+                return Err(ParseError {
+                    column,
+                    message: format!("unexpected character '{c}'"),
+                });
+            }
+        };
+        tokens.push(Token { kind, column });
+        idx += len;
+    }
+    tokens.push(Token {
+        kind: TokenKind::End,
+        column: chars.len() + 1,
+    });
+    Ok(tokens)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$' || c == '@'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn lex_identifier(chars: &[char], start: usize) -> Result<(TokenKind, usize), ParseError> {
+    let mut end = start + 1;
+    while end < chars.len() && is_ident_continue(chars[end]) {
+        end += 1;
+    }
+    let raw: String = chars[start..end].iter().collect();
+    // `$data`, `$index` and `$root` are templating scopes; any other `$`/`@` prefix is the
+    // legacy path marker and is dropped.
+    let name = match raw.as_str() {
+        "$data" | "$index" | "$root" => raw.clone(),
+        other => other.trim_start_matches(['$', '@']).to_string(),
+    };
+    if name.is_empty() {
+        return Err(ParseError {
+            column: start + 1,
+            message: format!("expected identifier after '{raw}'"),
+        });
+    }
+    let kind = match name.to_ascii_lowercase().as_str() {
+        "true" => TokenKind::Literal(Value::Bool(true)),
+        "false" => TokenKind::Literal(Value::Bool(false)),
+        "null" => TokenKind::Literal(Value::Null),
+        _ => TokenKind::Ident(name),
+    };
+    Ok((kind, end - start))
+}
+
+fn lex_number(
+    chars: &[char],
+    start: usize,
+    integer_only: bool,
+) -> Result<(TokenKind, usize), ParseError> {
+    let mut end = start;
+    let mut seen_dot = integer_only;
+    let mut seen_exp = integer_only;
+    while end < chars.len() {
+        let c = chars[end];
+        if c.is_ascii_digit() {
+            end += 1;
+        } else if c == '.' && !seen_dot && !seen_exp {
+            // A dot followed by a non-digit is member access, not a fraction.
+            if !chars.get(end + 1).is_some_and(|n| n.is_ascii_digit()) {
+                break;
+            }
+            seen_dot = true;
+            end += 1;
+        } else if (c == 'e' || c == 'E') && !seen_exp {
+            seen_exp = true;
+            end += 1;
+            if matches!(chars.get(end), Some('+') | Some('-')) {
+                end += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let raw: String = chars[start..end].iter().collect();
+    let value = if !raw.contains(['.', 'e', 'E']) {
+        raw.parse::<i64>().ok().map(Value::from)
+    } else {
+        None
+    }
+    .or_else(|| {
+        raw.parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+    })
+    .ok_or_else(|| ParseError {
+        column: start + 1,
+        message: format!("invalid number '{raw}'"),
+    })?;
+    Ok((TokenKind::Literal(value), end - start))
+}
+
+fn lex_string(chars: &[char], start: usize) -> Result<(TokenKind, usize), ParseError> {
+    let quote = chars[start];
+    let mut out = String::new();
+    let mut idx = start + 1;
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == quote {
+            return Ok((TokenKind::Str(out), idx + 1 - start));
+        }
+        if c != '\\' {
+            out.push(c);
+            idx += 1;
+            continue;
+        }
+        let escape_column = idx + 1;
+        let Some(&escaped) = chars.get(idx + 1) else {
+            break;
+        };
+        match escaped {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            '0' => out.push('\0'),
+            '\\' | '"' | '\'' | '/' => out.push(escaped),
+            'u' => {
+                let hex: String = chars.iter().skip(idx + 2).take(4).collect();
+                let decoded = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 4)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| ParseError {
+                        column: escape_column,
+                        message: format!("invalid unicode escape '\\u{hex}'"),
+                    })?;
+                out.push(decoded);
+                idx += 4;
+            }
+            other => {
+                return Err(ParseError {
+                    column: escape_column,
+                    message: format!("invalid escape '\\{other}'"),
+                });
+            }
+        }
+        idx += 2;
+    }
+    Err(ParseError {
+        column: start + 1,
+        message: "unterminated string literal".to_string(),
+    })
+}
+
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Literal(value) => format!("literal {value}"),
+        TokenKind::Str(s) => format!("string \"{s}\""),
+        TokenKind::Ident(name) => format!("identifier '{name}'"),
+        TokenKind::End => "end of expression".to_string(),
+        other => format!("'{}'", symbol(other)),
+    }
+}
+
+fn symbol(kind: &TokenKind) -> &'static str {
+    match kind {
+        TokenKind::LParen => "(",
+        TokenKind::RParen => ")",
+        TokenKind::LBracket => "[",
+        TokenKind::RBracket => "]",
+        TokenKind::Dot => ".",
+        TokenKind::Comma => ",",
+        TokenKind::Question => "?",
+        TokenKind::Colon => ":",
+        TokenKind::Not => "!",
+        TokenKind::Eq => "==",
+        TokenKind::Ne => "!=",
+        TokenKind::Lt => "<",
+        TokenKind::Le => "<=",
+        TokenKind::Gt => ">",
+        TokenKind::Ge => ">=",
+        TokenKind::And => "&&",
+        TokenKind::Or => "||",
+        TokenKind::Plus => "+",
+        TokenKind::Minus => "-",
+        TokenKind::Star => "*",
+        TokenKind::Slash => "/",
+        TokenKind::Percent => "%",
+        _ => "?",
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        if self.eat(&kind) {
+            return Ok(());
+        }
+        let found = self.peek();
+        Err(ParseError {
+            column: found.column,
+            message: format!(
+                "expected '{}' but found {}",
+                symbol(&kind),
+                describe(&found.kind)
+            ),
+        })
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expr, ParseError> {
+        let condition = self.parse_binary(0)?;
+        if !self.eat(&TokenKind::Question) {
+            return Ok(condition);
+        }
+        let then_branch = self.parse_conditional()?;
+        self.expect(TokenKind::Colon)?;
+        let else_branch = self.parse_conditional()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then_branch),
+            Box::new(else_branch),
+        ))
+    }
+
+    /// Precedence climbing over the left-associative binary operators.
+    fn parse_binary(&mut self, min_level: u8) -> Result<Expr, ParseError> {
+        let mut left = self.parse_unary()?;
+        while let Some((op, level)) = binary_op(&self.peek().kind) {
+            if level < min_level {
+                break;
+            }
+            self.advance();
+            let right = self.parse_binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat(&TokenKind::Not) {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&TokenKind::Minus) {
+            return Ok(Expr::Unary(UnaryOp::Negate, Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&TokenKind::Plus) {
+            return self.parse_unary();
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(&TokenKind::Dot) {
+                let token = self.advance();
+                let name = match token.kind {
+                    TokenKind::Ident(name) => name,
+                    // Numeric segments keep the dotted array form (`items.0.name`) working.
+                    TokenKind::Literal(Value::Number(n)) if n.is_u64() => n.to_string(),
+                    TokenKind::Literal(Value::Bool(b)) => b.to_string(),
+                    TokenKind::Literal(Value::Null) => "null".to_string(),
+                    other => {
+                        return Err(ParseError {
+                            column: token.column,
+                            message: format!("expected member name but found {}", describe(&other)),
+                        });
+                    }
+                };
+                expr = Expr::Member(Box::new(expr), name);
+            } else if self.eat(&TokenKind::LBracket) {
+                let index = self.parse_conditional()?;
+                self.expect(TokenKind::RBracket)?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Literal(value) => Ok(Expr::Literal(value)),
+            TokenKind::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            TokenKind::Ident(name) if self.eat(&TokenKind::LParen) => {
+                let mut args = Vec::new();
+                if !self.eat(&TokenKind::RParen) {
+                    loop {
+                        args.push(self.parse_conditional()?);
+                        if self.eat(&TokenKind::RParen) {
+                            break;
+                        }
+                        self.expect(TokenKind::Comma)?;
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            TokenKind::Ident(name) => Ok(Expr::Identifier(name)),
+            TokenKind::LParen => {
+                let inner = self.parse_conditional()?;
+                self.expect(TokenKind::RParen)?;
+                Ok(inner)
+            }
+            other => Err(ParseError {
+                column: token.column,
+                message: format!("unexpected {}", describe(&other)),
+            }),
+        }
+    }
+}
+
+fn binary_op(kind: &TokenKind) -> Option<(BinaryOp, u8)> {
+    let op = match kind {
+        TokenKind::Or => (BinaryOp::Or, 0),
+        TokenKind::And => (BinaryOp::And, 1),
+        TokenKind::Eq => (BinaryOp::Eq, 2),
+        TokenKind::Ne => (BinaryOp::Ne, 2),
+        TokenKind::Lt => (BinaryOp::Lt, 3),
+        TokenKind::Le => (BinaryOp::Le, 3),
+        TokenKind::Gt => (BinaryOp::Gt, 3),
+        TokenKind::Ge => (BinaryOp::Ge, 3),
+        TokenKind::Plus => (BinaryOp::Add, 4),
+        TokenKind::Minus => (BinaryOp::Sub, 4),
+        TokenKind::Star => (BinaryOp::Mul, 5),
+        TokenKind::Slash => (BinaryOp::Div, 5),
+        TokenKind::Percent => (BinaryOp::Rem, 5),
+        _ => return None,
+    };
+    Some(op)
+}
@@ -0,0 +1,218 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+use crate::compatibility::{self, format_version};
+use crate::model::{PatchOp, PatchOperation, RuleLevel, ValidationIssue};
+use crate::render::validate_card;
+use crate::rules;
+
+/// Upper bound on fixes applied to one card, in case a fix fails to clear its issue.
+const MAX_FIXES: usize = 256;
+
+/// Attaches a JSON Patch to each issue with an obvious repair: a missing or too-old
+/// `version`, a wrong root `type`, a missing input or Submit/Execute action id, duplicate
+/// ids (renamed with a numeric suffix) and `Action.ToggleVisibility` without targets (removed).
+pub(crate) fn suggest(card: &Value, issues: &mut [ValidationIssue]) {
+    let mut ids = BTreeSet::new();
+    collect_ids(card, &mut ids);
+    let minimum = format_version(compatibility::minimum_version(card));
+    for issue in issues.iter_mut() {
+        let node = |path: &str| card.pointer(path);
+        issue.fix = match issue.code.as_str() {
+            "missing-version" => vec![set(PatchOp::Add, "/version", minimum.clone())],
+            "requires-newer-version" => vec![set(PatchOp::Replace, "/version", minimum.clone())],
+            "invalid-type" => vec![set(PatchOp::Add, "/type", "AdaptiveCard")],
+            "missing-id" | "missing-action-id" => {
+                let kind = node(&issue.path)
+                    .and_then(|node| node.get("type"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("input");
+                let base = kind
+                    .trim_start_matches("Input.")
+                    .trim_start_matches("Action.")
+                    .to_ascii_lowercase();
+                let id = unique_id(&base, &mut ids);
+                vec![set(PatchOp::Add, &format!("{}/id", issue.path), id)]
+            }
+            "duplicate-id" | "duplicate-action-id" | "duplicate-element-id" => {
+                match node(&issue.path)
+                    .and_then(|node| node.get("id"))
+                    .and_then(Value::as_str)
+                {
+                    Some(id) => {
+                        let id = unique_id(id, &mut ids);
+                        vec![set(PatchOp::Replace, &format!("{}/id", issue.path), id)]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            "missing-target-elements" | "empty-target-elements" => vec![PatchOperation {
+                op: PatchOp::Remove,
+                path: issue.path.clone(),
+                value: None,
+            }],
+            _ => Vec::new(),
+        };
+    }
+}
+
+/// Applies fixes one at a time, re-validating after each so later paths stay accurate,
+/// until no fixable issue remains. Returns the repaired issues in order.
+pub(crate) fn repair(
+    card: &mut Value,
+    levels: &BTreeMap<String, RuleLevel>,
+) -> Vec<ValidationIssue> {
+    let mut applied: Vec<ValidationIssue> = Vec::new();
+    while applied.len() < MAX_FIXES {
+        let mut issues = rules::apply(validate_card(card), levels);
+        suggest(card, &mut issues);
+        let Some(issue) = issues.into_iter().find(|issue| !issue.fix.is_empty()) else {
+            break;
+        };
+        if applied.contains(&issue) || apply_patch(card, &issue.fix).is_err() {
+            break;
+        }
+        applied.push(issue);
+    }
+    applied
+}
+
+/// Applies `add`, `remove` and `replace` operations in order, stopping at the first that
+/// does not apply.
+pub(crate) fn apply_patch(card: &mut Value, patch: &[PatchOperation]) -> Result<(), String> {
+    for operation in patch {
+        let (parent, last) = match operation.path.rfind('/') {
+            Some(index) => operation.path.split_at(index),
+            None => return Err(format!("invalid pointer `{}`", operation.path)),
+        };
+        let key = last[1..].replace("~1", "/").replace("~0", "~");
+        let target = card
+            .pointer_mut(parent)
+            .ok_or_else(|| format!("no value at `{parent}`"))?;
+        let value = || operation.value.clone().unwrap_or(Value::Null);
+        let missing = || format!("no value at `{}`", operation.path);
+        match (target, operation.op) {
+            (Value::Object(map), PatchOp::Add) => {
+                map.insert(key, value());
+            }
+            (Value::Object(map), PatchOp::Replace) => {
+                *map.get_mut(&key).ok_or_else(missing)? = value();
+            }
+            (Value::Object(map), PatchOp::Remove) => {
+                map.remove(&key).ok_or_else(missing)?;
+            }
+            (Value::Array(items), op) => {
+                let index = if key == "-" && op == PatchOp::Add {
+                    items.len()
+                } else {
+                    key.parse::<usize>().map_err(|_| missing())?
+                };
+                match op {
+                    PatchOp::Add if index <= items.len() => items.insert(index, value()),
+                    PatchOp::Replace if index < items.len() => items[index] = value(),
+                    PatchOp::Remove if index < items.len() => {
+                        items.remove(index);
+                    }
+                    _ => return Err(missing()),
+                }
+            }
+            _ => return Err(missing()),
+        }
+    }
+    Ok(())
+}
+
+fn set(op: PatchOp, path: &str, value: impl Into<Value>) -> PatchOperation {
+    PatchOperation {
+        op,
+        path: path.to_string(),
+        value: Some(value.into()),
+    }
+}
+
+/// `base`, or `base_2`, `base_3`, ... — the first not yet taken, which is then reserved.
+fn unique_id(base: &str, ids: &mut BTreeSet<String>) -> String {
+    let mut candidate = base.to_string();
+    let mut suffix = 2;
+    while ids.contains(&candidate) {
+        candidate = format!("{base}_{suffix}");
+        suffix += 1;
+    }
+    ids.insert(candidate.clone());
+    candidate
+}
+
+fn collect_ids(value: &Value, ids: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(id) = map.get("id").and_then(Value::as_str) {
+                ids.insert(id.to_string());
+            }
+            map.values().for_each(|child| collect_ids(child, ids));
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_ids(item, ids)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn repairs_fixable_issues_in_order() {
+        let mut card = json!({
+            "type": "AdaptiveCard",
+            "body": [
+                { "type": "Input.Text", "label": "Name" },
+                { "type": "Input.Text", "id": "email", "label": "Email" },
+                { "type": "Input.Number", "id": "email", "label": "Age" }
+            ],
+            "actions": [
+                { "type": "Action.ToggleVisibility", "title": "More", "targetElements": [] },
+                { "type": "Action.Submit", "title": "Send" }
+            ]
+        });
+        let applied = repair(&mut card, &BTreeMap::new());
+        let codes: Vec<&str> = applied.iter().map(|issue| issue.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            [
+                "missing-version",
+                "empty-target-elements",
+                "missing-action-id",
+                "missing-id",
+                "duplicate-id"
+            ]
+        );
+        assert_eq!(
+            card,
+            json!({
+                "type": "AdaptiveCard",
+                "version": "1.3",
+                "body": [
+                    { "type": "Input.Text", "label": "Name", "id": "text" },
+                    { "type": "Input.Text", "id": "email", "label": "Email" },
+                    { "type": "Input.Number", "id": "email_2", "label": "Age" }
+                ],
+                "actions": [ { "type": "Action.Submit", "title": "Send", "id": "submit" } ]
+            })
+        );
+    }
+
+    #[test]
+    fn patch_reports_missing_targets() {
+        let mut card = json!({ "body": [1] });
+        let remove = |path: &str| PatchOperation {
+            op: PatchOp::Remove,
+            path: path.into(),
+            value: None,
+        };
+        assert!(apply_patch(&mut card, &[remove("/body/0")]).is_ok());
+        assert!(apply_patch(&mut card, &[remove("/body/0")]).is_err());
+        assert!(apply_patch(&mut card, &[set(PatchOp::Add, "/body/-", 2)]).is_ok());
+        assert_eq!(card, json!({ "body": [2] }));
+    }
+}
@@ -0,0 +1,250 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use serde_json::Value;
+
+use crate::datetime;
+use crate::expression::{as_number, stringify_value, to_f64};
+use crate::i18n;
+use crate::locale::{self, Locale};
+
+/// Registers the card-oriented helpers on top of the Handlebars built-ins, which already
+/// provide `eq`, `ne`, `gt`, `gte`, `lt`, `lte`, `and`, `or`, `not` and `len`.
+pub(crate) fn register_helpers(engine: &mut Handlebars<'_>) {
+    engine.register_helper("json", Box::new(ValueHelper(json)));
+    engine.register_helper("default", Box::new(ValueHelper(default)));
+    engine.register_helper("formatDate", Box::new(ValueHelper(format_date)));
+    engine.register_helper("formatTime", Box::new(ValueHelper(format_time)));
+    engine.register_helper("formatNumber", Box::new(ValueHelper(format_number)));
+    engine.register_helper("formatPercent", Box::new(ValueHelper(format_percent)));
+    engine.register_helper("formatCurrency", Box::new(ValueHelper(format_currency)));
+    engine.register_helper("formatDuration", Box::new(ValueHelper(format_duration)));
+    engine.register_helper("truncate", Box::new(ValueHelper(truncate)));
+    engine.register_helper("pluralize", Box::new(ValueHelper(pluralize)));
+    engine.register_helper("t", Box::new(TranslateHelper));
+}
+
+/// Adapts a function over the helper's positional parameters into a Handlebars helper,
+/// usable both inline (`{{truncate text 20}}`) and as a subexpression. The function also
+/// receives the invocation's locale and time zone from the `i18n` entry of the context.
+struct ValueHelper(fn(&[&Value], &Locale) -> Result<Value, String>);
+
+impl HelperDef for ValueHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let params: Vec<&Value> = h.params().iter().map(|p| p.value()).collect();
+        (self.0)(&params, &context_locale(ctx))
+            .map(ScopedJson::Derived)
+            .map_err(|msg| RenderErrorReason::Other(format!("{}: {msg}", h.name())).into())
+    }
+}
+
+/// `{{t "cart.items" count=3}}`: the message from the invocation's string tables, read
+/// from the `i18n` entry of the render context. Arguments come from an optional object
+/// parameter and the hash; unknown keys render as the key itself.
+struct TranslateHelper;
+
+impl HelperDef for TranslateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let Some(key) = h.param(0).and_then(|p| p.value().as_str()) else {
+            return Err(RenderErrorReason::Other("t: expects a string key".into()).into());
+        };
+        let mut args = h
+            .param(1)
+            .and_then(|p| p.value().as_object())
+            .cloned()
+            .unwrap_or_default();
+        for (name, value) in h.hash() {
+            args.insert(name.to_string(), value.value().clone());
+        }
+        let i18n = ctx.data().get("i18n");
+        let locale = i18n
+            .and_then(|i| i.get("locale"))
+            .and_then(Value::as_str)
+            .unwrap_or(i18n::DEFAULT_LOCALE);
+        let text = match i18n
+            .and_then(|i| i.get("strings"))
+            .and_then(|strings| strings.get(key))
+            .and_then(Value::as_str)
+        {
+            Some(pattern) => i18n::format_message(pattern, &args, locale),
+            None => key.to_string(),
+        };
+        Ok(ScopedJson::Derived(Value::String(text)))
+    }
+}
+
+fn context_locale(ctx: &Context) -> Locale {
+    let i18n = ctx.data().get("i18n");
+    let field = |name: &str| {
+        i18n.and_then(|i| i.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    Locale {
+        tag: field("locale").unwrap_or_else(|| i18n::DEFAULT_LOCALE.to_string()),
+        time_zone: field("time_zone"),
+    }
+}
+
+/// `{{{json value}}}`: the value serialized as JSON text.
+fn json(params: &[&Value], _: &Locale) -> Result<Value, String> {
+    let value = params.first().copied().unwrap_or(&Value::Null);
+    serde_json::to_string(value)
+        .map(Value::String)
+        .map_err(|err| err.to_string())
+}
+
+/// `{{default value fallback}}`: `fallback` when `value` is null, missing or empty.
+fn default(params: &[&Value], _: &Locale) -> Result<Value, String> {
+    let [value, fallback] = params else {
+        return Err("expects a value and a fallback".into());
+    };
+    let empty = match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    };
+    Ok(if empty {
+        (*fallback).clone()
+    } else {
+        (*value).clone()
+    })
+}
+
+/// `{{formatDate timestamp "long"}}`: a `short`/`medium`/`long`/`full` date style or a
+/// custom pattern (default `yyyy-MM-dd`), in the invocation's locale and time zone.
+fn format_date(params: &[&Value], locale: &Locale) -> Result<Value, String> {
+    let dt = timestamp(params)?;
+    let style = params
+        .get(1)
+        .and_then(|v| v.as_str())
+        .unwrap_or("yyyy-MM-dd");
+    locale.format_date(dt, style).map(Value::String)
+}
+
+/// `{{formatTime timestamp}}`: a `short`/`medium` time style (default `short`) or a
+/// custom pattern.
+fn format_time(params: &[&Value], locale: &Locale) -> Result<Value, String> {
+    let dt = timestamp(params)?;
+    let style = params.get(1).and_then(|v| v.as_str()).unwrap_or("short");
+    locale.format_time(dt, style).map(Value::String)
+}
+
+fn timestamp(params: &[&Value]) -> Result<datetime::DateTime, String> {
+    let Some(raw) = params.first().and_then(|v| v.as_str()) else {
+        return Err("expects an ISO 8601 timestamp".into());
+    };
+    datetime::parse(raw).ok_or_else(|| format!("invalid timestamp \"{raw}\""))
+}
+
+/// `{{formatNumber value 2}}`: grouped thousands with a fixed number of decimals
+/// (default 0), using the locale's separators.
+fn format_number(params: &[&Value], locale: &Locale) -> Result<Value, String> {
+    let value = number(params)?;
+    Ok(Value::String(
+        locale.format_number(value, precision(params, 1).unwrap_or(0)),
+    ))
+}
+
+/// `{{formatPercent 0.25}}` → `25%`, with optional decimals.
+fn format_percent(params: &[&Value], locale: &Locale) -> Result<Value, String> {
+    let value = number(params)?;
+    Ok(Value::String(
+        locale.format_percent(value, precision(params, 1).unwrap_or(0)),
+    ))
+}
+
+/// `{{formatCurrency total "EUR"}}`: the amount with the currency's symbol and minor
+/// units, or an explicit number of decimals as the third parameter.
+fn format_currency(params: &[&Value], locale: &Locale) -> Result<Value, String> {
+    let value = number(params)?;
+    let code = params
+        .get(1)
+        .and_then(|v| v.as_str())
+        .ok_or("expects an ISO 4217 currency code")?;
+    Ok(Value::String(locale.format_currency(
+        value,
+        code,
+        precision(params, 2),
+    )))
+}
+
+/// `{{formatDuration "PT1H30M"}}` or `{{formatDuration ms "short"}}`.
+fn format_duration(params: &[&Value], locale: &Locale) -> Result<Value, String> {
+    let millis = params
+        .first()
+        .and_then(|v| locale::parse_duration(v))
+        .ok_or("expects milliseconds or an ISO 8601 duration")?;
+    let short = params.get(1).and_then(|v| v.as_str()) == Some("short");
+    Ok(Value::String(locale.format_duration(millis, short)))
+}
+
+fn number(params: &[&Value]) -> Result<f64, String> {
+    params
+        .first()
+        .and_then(|v| as_number(v))
+        .map(to_f64)
+        .ok_or_else(|| "expects a number".into())
+}
+
+fn precision(params: &[&Value], index: usize) -> Option<usize> {
+    params
+        .get(index)
+        .and_then(|v| v.as_u64())
+        .map(|p| p.min(15) as usize)
+}
+
+/// `{{truncate text 40}}`: at most `length` characters, ending in `…` (or the optional
+/// third parameter) when shortened.
+fn truncate(params: &[&Value], _: &Locale) -> Result<Value, String> {
+    let text = params
+        .first()
+        .map(|v| stringify_value(v))
+        .unwrap_or_default();
+    let length = params
+        .get(1)
+        .and_then(|v| v.as_u64())
+        .ok_or("expects a maximum length")? as usize;
+    let suffix = params.get(2).and_then(|v| v.as_str()).unwrap_or("…");
+    if text.chars().count() <= length {
+        return Ok(Value::String(text));
+    }
+    let keep = length.saturating_sub(suffix.chars().count());
+    let mut out: String = text.chars().take(keep).collect();
+    out.push_str(suffix);
+    Ok(Value::String(out))
+}
+
+/// `{{pluralize count "item"}}` or `{{pluralize count "child" "children"}}`: the singular
+/// form when `count` is 1, otherwise the plural (default: singular + `s`).
+fn pluralize(params: &[&Value], _: &Locale) -> Result<Value, String> {
+    let count = params.first().ok_or("expects a count")?;
+    let singular = params
+        .get(1)
+        .and_then(|v| v.as_str())
+        .ok_or("expects a singular form")?;
+    let one = as_number(count).is_some_and(|n| to_f64(n) == 1.0);
+    let word = if one {
+        singular.to_string()
+    } else {
+        params
+            .get(2)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{singular}s"))
+    };
+    Ok(Value::String(word))
+}
@@ -0,0 +1,232 @@
+use serde_json::Value;
+
+use crate::compatibility::{self, Minor};
+use crate::model::{CardFeatureSummary, HostProfile, ValidationIssue};
+
+/// What a delivery channel can render.
+pub(crate) struct HostCapabilities {
+    pub name: &'static str,
+    /// Highest Adaptive Card version the host renders.
+    pub max_version: Minor,
+    pub elements: &'static [&'static str],
+    pub actions: &'static [&'static str],
+    /// Largest serialized card the host accepts, in bytes.
+    pub max_payload_bytes: usize,
+    pub action_execute: bool,
+    pub media: bool,
+}
+
+const ELEMENTS_1_2: &[&str] = &[
+    "TextBlock",
+    "Image",
+    "Container",
+    "ColumnSet",
+    "Column",
+    "FactSet",
+    "ImageSet",
+    "ActionSet",
+    "RichTextBlock",
+    "TextRun",
+    "Input.Text",
+    "Input.Number",
+    "Input.Date",
+    "Input.Time",
+    "Input.Toggle",
+    "Input.ChoiceSet",
+];
+
+const ELEMENTS_ALL: &[&str] = &[
+    "TextBlock",
+    "Image",
+    "Media",
+    "Container",
+    "ColumnSet",
+    "Column",
+    "FactSet",
+    "ImageSet",
+    "ActionSet",
+    "RichTextBlock",
+    "TextRun",
+    "Table",
+    "TableRow",
+    "TableCell",
+    "Input.Text",
+    "Input.Number",
+    "Input.Date",
+    "Input.Time",
+    "Input.Toggle",
+    "Input.ChoiceSet",
+];
+
+const ACTIONS_ALL: &[&str] = &[
+    "Action.OpenUrl",
+    "Action.Submit",
+    "Action.ShowCard",
+    "Action.ToggleVisibility",
+    "Action.Execute",
+];
+
+const TEAMS: HostCapabilities = HostCapabilities {
+    name: "Microsoft Teams",
+    max_version: 5,
+    elements: ELEMENTS_ALL,
+    actions: ACTIONS_ALL,
+    max_payload_bytes: 28 * 1024,
+    action_execute: true,
+    media: true,
+};
+
+const WEBEX: HostCapabilities = HostCapabilities {
+    name: "Webex",
+    max_version: 3,
+    elements: ELEMENTS_1_2,
+    actions: &[
+        "Action.OpenUrl",
+        "Action.Submit",
+        "Action.ShowCard",
+        "Action.ToggleVisibility",
+    ],
+    max_payload_bytes: 22 * 1024,
+    action_execute: false,
+    media: false,
+};
+
+const OUTLOOK: HostCapabilities = HostCapabilities {
+    name: "Outlook Actionable Messages",
+    max_version: 4,
+    elements: ELEMENTS_1_2,
+    actions: &[
+        "Action.OpenUrl",
+        "Action.ShowCard",
+        "Action.ToggleVisibility",
+        "Action.Execute",
+        "Action.Http",
+    ],
+    max_payload_bytes: 28 * 1024,
+    action_execute: true,
+    media: false,
+};
+
+const WEB_CHAT: HostCapabilities = HostCapabilities {
+    name: "Web Chat",
+    max_version: 6,
+    elements: ELEMENTS_ALL,
+    actions: ACTIONS_ALL,
+    max_payload_bytes: 256 * 1024,
+    action_execute: true,
+    media: true,
+};
+
+pub(crate) fn capabilities(profile: &HostProfile) -> &'static HostCapabilities {
+    match profile {
+        HostProfile::Teams => &TEAMS,
+        HostProfile::Webex => &WEBEX,
+        HostProfile::Outlook => &OUTLOOK,
+        HostProfile::WebChat => &WEB_CHAT,
+    }
+}
+
+impl HostCapabilities {
+    pub(crate) fn supports_element(&self, kind: &str) -> bool {
+        kind == "AdaptiveCard" || (self.elements.contains(&kind) && (kind != "Media" || self.media))
+    }
+
+    pub(crate) fn supports_action(&self, kind: &str) -> bool {
+        self.actions.contains(&kind) && (kind != "Action.Execute" || self.action_execute)
+    }
+}
+
+/// Reports what the host cannot handle: a declared or required version above its maximum,
+/// and each element and action it cannot render (unless the node declares a `fallback`).
+/// The payload limit is checked with the card's size report.
+pub(crate) fn validate_for_host(
+    card: &Value,
+    features: &CardFeatureSummary,
+    host: &HostCapabilities,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let max = compatibility::format_version(host.max_version);
+    let declared = features.version.as_deref();
+    if let Some(version) = declared
+        && compatibility::parse_version(version).is_some_and(|minor| minor > host.max_version)
+    {
+        issues.push(ValidationIssue {
+            code: "host-unsupported-version".into(),
+            message: format!(
+                "{} renders Adaptive Cards up to version {max}; the card declares {version}",
+                host.name
+            ),
+            path: "/version".into(),
+            ..Default::default()
+        });
+    } else if let Some(minimum) = features.minimum_version.as_deref()
+        && compatibility::parse_version(minimum).is_some_and(|minor| minor > host.max_version)
+    {
+        issues.push(ValidationIssue {
+            code: "host-unsupported-version".into(),
+            message: format!(
+                "{} renders Adaptive Cards up to version {max}; the card needs {minimum}",
+                host.name
+            ),
+            path: "/".into(),
+            ..Default::default()
+        });
+    }
+
+    let unsupported_elements: Vec<&str> = features
+        .used_elements
+        .iter()
+        .map(String::as_str)
+        .filter(|kind| !host.supports_element(kind))
+        .collect();
+    let unsupported_actions: Vec<&str> = features
+        .used_actions
+        .iter()
+        .map(String::as_str)
+        .filter(|kind| !host.supports_action(kind))
+        .collect();
+    if !unsupported_elements.is_empty() || !unsupported_actions.is_empty() {
+        let mut nodes = Vec::new();
+        typed_nodes(card, String::new(), &mut nodes);
+        for (kind, path) in nodes {
+            let (code, what) = if unsupported_actions.contains(&kind) {
+                ("host-unsupported-action", "action")
+            } else if unsupported_elements.contains(&kind) {
+                ("host-unsupported-element", "element")
+            } else {
+                continue;
+            };
+            issues.push(ValidationIssue {
+                code: code.into(),
+                message: format!("{} cannot render the `{kind}` {what}", host.name),
+                path: if path.is_empty() { "/".into() } else { path },
+                ..Default::default()
+            });
+        }
+    }
+
+    issues
+}
+
+/// Type and JSON pointer of every typed object without a `fallback`, outside action `data`.
+fn typed_nodes<'v>(value: &'v Value, path: String, out: &mut Vec<(&'v str, String)>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(kind) = map.get("type").and_then(Value::as_str)
+                && !map.contains_key("fallback")
+            {
+                out.push((kind, path.clone()));
+            }
+            for (key, child) in map.iter().filter(|(key, _)| *key != "data") {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                typed_nodes(child, format!("{path}/{key}"), out);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                typed_nodes(item, format!("{path}/{index}"), out);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::expression::stringify_value;
+use crate::model::AdaptiveCardInvocation;
+
+pub(crate) const DEFAULT_LOCALE: &str = "en";
+
+/// Strings for the invocation's locale, merged along its fallback chain.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Translations {
+    /// Most specific locale of the chain, used for plural rules.
+    pub locale: String,
+    /// Locales searched, most specific first (`nl-BE`, `nl`, `en`).
+    pub chain: Vec<String>,
+    /// Dotted keys to ICU-style message patterns.
+    pub strings: BTreeMap<String, String>,
+}
+
+impl Translations {
+    /// Formats the message for `key`, or `None` when no table in the chain defines it.
+    pub(crate) fn translate(&self, key: &str, args: &Map<String, Value>) -> Option<String> {
+        let pattern = self.strings.get(key)?;
+        Some(format_message(pattern, args, &self.locale))
+    }
+
+    /// Adds the entries of a (possibly nested) table without overriding existing keys, so
+    /// tables must be merged from the most specific locale down.
+    pub(crate) fn merge(&mut self, table: &Value) {
+        flatten(table, String::new(), &mut self.strings);
+    }
+}
+
+fn flatten(value: &Value, prefix: String, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, entry) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(entry, key, out);
+            }
+        }
+        Value::String(text) => {
+            out.entry(prefix).or_insert_with(|| text.clone());
+        }
+        _ => {}
+    }
+}
+
+/// The requested locale: `session.locale`, then the envelope's `locale` attribute, then its
+/// `i18n_id` when that is a plain tag rather than an opaque `i18n:` identifier.
+pub(crate) fn requested_locale(inv: &AdaptiveCardInvocation) -> Option<String> {
+    if let Some(locale) = inv.session.get("locale").and_then(Value::as_str) {
+        return Some(locale.to_string());
+    }
+    let ctx = &inv.envelope.as_ref()?.ctx;
+    ctx.attributes
+        .get("locale")
+        .or(ctx.i18n_id.as_ref().filter(|id| !id.starts_with("i18n:")))
+        .cloned()
+}
+
+/// `nl-BE` → `["nl-BE", "nl", <default>]`: each tag with its last subtag removed in turn,
+/// then the default locale. `_` separators are accepted and normalised to `-`.
+pub(crate) fn locale_chain(requested: Option<&str>, default: &str) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    let mut push = |tag: &str| {
+        if !tag.is_empty() && !chain.iter().any(|c| c.eq_ignore_ascii_case(tag)) {
+            chain.push(tag.to_string());
+        }
+    };
+    for start in [requested.unwrap_or_default(), default] {
+        let mut tag = start.trim().replace('_', "-");
+        loop {
+            push(&tag);
+            match tag.rfind('-') {
+                Some(pos) => tag.truncate(pos),
+                None => break,
+            }
+        }
+    }
+    chain
+}
+
+/// Formats an ICU MessageFormat-style pattern: `{name}` arguments, `{n, plural, =0 {…}
+/// one {# item} other {# items}}` with an optional `offset:`, and `{x, select, a {…}
+/// other {…}}`. A quote starts a literal run when followed by `{`, `}` or `#`; `''` is a
+/// single quote. Unknown arguments are left as written.
+pub(crate) fn format_message(pattern: &str, args: &Map<String, Value>, locale: &str) -> String {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut pos = 0;
+    format_run(&chars, &mut pos, args, locale, None)
+}
+
+/// Formats until the end of input or an unmatched `}`. `count` is the number `#` stands
+/// for inside a plural branch.
+fn format_run(
+    chars: &[char],
+    pos: &mut usize,
+    args: &Map<String, Value>,
+    locale: &str,
+    count: Option<&str>,
+) -> String {
+    let mut out = String::new();
+    while let Some(&c) = chars.get(*pos) {
+        match c {
+            '}' => break,
+            '\'' => {
+                *pos += 1;
+                match chars.get(*pos) {
+                    Some('\'') => {
+                        out.push('\'');
+                        *pos += 1;
+                    }
+                    Some('{' | '}' | '#') => {
+                        while let Some(&q) = chars.get(*pos) {
+                            *pos += 1;
+                            if q == '\'' {
+                                if chars.get(*pos) == Some(&'\'') {
+                                    out.push('\'');
+                                    *pos += 1;
+                                    continue;
+                                }
+                                break;
+                            }
+                            out.push(q);
+                        }
+                    }
+                    _ => out.push('\''),
+                }
+            }
+            '#' if count.is_some() => {
+                out.push_str(count.unwrap_or_default());
+                *pos += 1;
+            }
+            '{' => {
+                let start = *pos;
+                *pos += 1;
+                match format_argument(chars, pos, args, locale) {
+                    Some(text) => out.push_str(&text),
+                    None => {
+                        let end = skip_block(chars, start);
+                        out.extend(&chars[start..end]);
+                        *pos = end;
+                    }
+                }
+            }
+            c => {
+                out.push(c);
+                *pos += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Formats one `{...}` argument with `pos` just past its `{`, leaving `pos` past the
+/// closing `}`. `None` when the argument is unknown or malformed.
+fn format_argument(
+    chars: &[char],
+    pos: &mut usize,
+    args: &Map<String, Value>,
+    locale: &str,
+) -> Option<String> {
+    let name = read_until(chars, pos, &[',', '}']);
+    let value = args.get(name.trim())?;
+    if chars.get(*pos) == Some(&'}') {
+        *pos += 1;
+        return Some(stringify_value(value));
+    }
+    *pos += 1;
+    let kind = read_until(chars, pos, &[',', '}']);
+    let kind = kind.trim();
+    if chars.get(*pos) != Some(&',') {
+        return None;
+    }
+    *pos += 1;
+    let mut offset = 0.0;
+    let mut branches: Vec<(String, usize)> = Vec::new();
+    loop {
+        skip_whitespace(chars, pos);
+        match chars.get(*pos)? {
+            '}' => {
+                *pos += 1;
+                break;
+            }
+            _ => {
+                let selector = read_until(chars, pos, &['{', '}', ' ', '\t', '\n']);
+                if let Some(raw) = selector.strip_prefix("offset:") {
+                    offset = raw.parse().ok()?;
+                    continue;
+                }
+                skip_whitespace(chars, pos);
+                if selector.is_empty() || chars.get(*pos) != Some(&'{') {
+                    return None;
+                }
+                branches.push((selector.to_string(), *pos + 1));
+                *pos = skip_block(chars, *pos);
+            }
+        }
+    }
+    let pick = |wanted: &str| {
+        branches
+            .iter()
+            .find(|(s, _)| s == wanted)
+            .map(|(_, at)| *at)
+    };
+    let (start, count) = match kind {
+        "plural" => {
+            let n = value.as_f64()?;
+            let exact = format!("={}", plain_number(n));
+            let shown = plain_number(n - offset);
+            let at = pick(&exact)
+                .or_else(|| pick(plural_category(locale, n - offset)))
+                .or_else(|| pick("other"))?;
+            (at, Some(shown))
+        }
+        "select" => {
+            let at = pick(&stringify_value(value)).or_else(|| pick("other"))?;
+            (at, None)
+        }
+        _ => return None,
+    };
+    let mut branch_pos = start;
+    Some(format_run(
+        chars,
+        &mut branch_pos,
+        args,
+        locale,
+        count.as_deref(),
+    ))
+}
+
+fn plain_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+fn read_until(chars: &[char], pos: &mut usize, stops: &[char]) -> String {
+    let start = *pos;
+    while chars.get(*pos).is_some_and(|c| !stops.contains(c)) {
+        *pos += 1;
+    }
+    chars[start..*pos].iter().collect()
+}
+
+fn skip_whitespace(chars: &[char], pos: &mut usize) {
+    while chars.get(*pos).is_some_and(|c| c.is_whitespace()) {
+        *pos += 1;
+    }
+}
+
+/// Index just past the `}` matching the `{` at `start` (or the end of input).
+fn skip_block(chars: &[char], start: usize) -> usize {
+    let mut depth = 0;
+    for (index, c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    chars.len()
+}
+
+/// CLDR cardinal plural category of `n` for the locale's language.
+pub(crate) fn plural_category(locale: &str, n: f64) -> &'static str {
+    let language = locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let integer = n.fract() == 0.0;
+    let i = n.abs().trunc() as u64;
+    let (mod10, mod100) = (i % 10, i % 100);
+    match language.as_str() {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" => "other",
+        "fr" | "pt" if i <= 1 => "one",
+        "fr" | "pt" => "other",
+        "ru" | "uk" | "be" if !integer => "other",
+        "ru" | "uk" | "be" if mod10 == 1 && mod100 != 11 => "one",
+        "ru" | "uk" | "be" if (2..=4).contains(&mod10) && !(12..=14).contains(&mod100) => "few",
+        "ru" | "uk" | "be" => "many",
+        "pl" if !integer => "other",
+        "pl" if i == 1 => "one",
+        "pl" if (2..=4).contains(&mod10) && !(12..=14).contains(&mod100) => "few",
+        "pl" => "many",
+        "cs" | "sk" if !integer => "many",
+        "cs" | "sk" if i == 1 => "one",
+        "cs" | "sk" if (2..=4).contains(&i) => "few",
+        "cs" | "sk" => "other",
+        _ if integer && i == 1 => "one",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn locale_chain_falls_back_to_language_then_default() {
+        assert_eq!(locale_chain(Some("nl-BE"), "en"), ["nl-BE", "nl", "en"]);
+        assert_eq!(locale_chain(Some("de_DE"), "de"), ["de-DE", "de"]);
+        assert_eq!(locale_chain(None, "en-GB"), ["en-GB", "en"]);
+    }
+
+    #[test]
+    fn formats_arguments_plurals_and_selects() {
+        let items = "{count, plural, =0 {no items} one {# item} other {# items}}";
+        let others = "{n, plural, offset:1 =0 {nobody} =1 {{name}} one {{name} and # other} other {{name} and # others}}";
+        assert_eq!(
+            format_message(others, &args(json!({"n": 2, "name": "Ada"})), "en"),
+            "Ada and 1 other"
+        );
+        assert_eq!(
+            format_message(others, &args(json!({"n": 3, "name": "Ada"})), "en"),
+            "Ada and 2 others"
+        );
+        assert_eq!(
+            format_message(items, &args(json!({"count": 0})), "en"),
+            "no items"
+        );
+        assert_eq!(
+            format_message(items, &args(json!({"count": 1})), "en"),
+            "1 item"
+        );
+        assert_eq!(
+            format_message(items, &args(json!({"count": 4})), "nl"),
+            "4 items"
+        );
+
+        let files = "{n, plural, one {# plik} few {# pliki} many {# plików} other {# pliku}}";
+        assert_eq!(
+            format_message(files, &args(json!({"n": 3})), "pl"),
+            "3 pliki"
+        );
+        assert_eq!(
+            format_message(files, &args(json!({"n": 12})), "pl"),
+            "12 plików"
+        );
+
+        let greeting = "{gender, select, female {Hallo Frau {name}} other {Hallo {name}}}";
+        assert_eq!(
+            format_message(
+                greeting,
+                &args(json!({"gender": "female", "name": "Ada"})),
+                "de"
+            ),
+            "Hallo Frau Ada"
+        );
+        assert_eq!(
+            format_message(
+                "It''s '{literal}' for {who} and {missing}",
+                &args(json!({"who": "you"})),
+                "en"
+            ),
+            "It's {literal} for you and {missing}"
+        );
+    }
+}
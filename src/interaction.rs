@@ -143,6 +143,10 @@ pub fn handle_interaction(
         card_features: resolved.features,
         validation_issues: resolved.validation_issues,
         telemetry_events,
+        binding_diagnostics: resolved.binding_diagnostics,
+        transformations: resolved.transformations,
+        applied_fixes: resolved.applied_fixes,
+        payload_size: resolved.payload_size,
     })
 }
 
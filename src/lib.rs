@@ -1,11 +1,27 @@
+mod accessibility;
 mod asset_resolver;
+mod compatibility;
+mod datetime;
+mod downgrade;
 mod error;
 mod expression;
+mod expression_functions;
+mod expression_parser;
+mod fixes;
+mod handlebars_helpers;
+mod host_profile;
+mod i18n;
 mod interaction;
+mod locale;
 mod model;
+mod payload_size;
+mod references;
 mod render;
+mod rules;
 mod state_store;
+mod template_cache;
 mod trace;
+mod url_safety;
 mod validation;
 
 use once_cell::sync::Lazy;
@@ -14,9 +30,15 @@ pub use asset_resolver::{
     register_host_asset_callback, register_host_asset_map, register_host_asset_resolver,
 };
 pub use error::ComponentError;
+pub use expression::{
+    AdaptiveExpressionEngine, ExpressionEngine, SimpleExpressionEngine, register_expression_engine,
+    set_default_expression_engine,
+};
+pub use expression_functions::{HostFunction, register_host_function};
 pub use interaction::handle_interaction;
 pub use model::*;
-pub use render::render_card;
+pub use render::{BindingContext, render_card};
+pub use rules::{ValidationRule, validation_rules};
 
 static COMPONENT_SCHEMA_JSON: Lazy<serde_json::Value> = Lazy::new(|| {
     serde_json::from_str(include_str!("../schemas/component.schema.json"))
@@ -227,13 +249,19 @@ pub fn handle_invocation(
     }
 
     let rendered = render_card(&invocation)?;
-    if invocation.validation_mode == ValidationMode::Error && !rendered.validation_issues.is_empty()
+    if invocation.validation_mode == ValidationMode::Error
+        && rendered
+            .validation_issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
     {
         return Err(ComponentError::CardValidation(rendered.validation_issues));
     }
     let rendered_card = match invocation.mode {
         InvocationMode::Validate => None,
-        InvocationMode::Render | InvocationMode::RenderAndValidate => Some(rendered.card),
+        InvocationMode::Render | InvocationMode::RenderAndValidate | InvocationMode::Fix => {
+            Some(rendered.card)
+        }
     };
 
     let mut telemetry_events = Vec::new();
@@ -258,6 +286,10 @@ pub fn handle_invocation(
         card_features: rendered.features,
         validation_issues: rendered.validation_issues,
         telemetry_events,
+        binding_diagnostics: rendered.binding_diagnostics,
+        transformations: rendered.transformations,
+        applied_fixes: rendered.applied_fixes,
+        payload_size: rendered.payload_size,
     })
 }
 
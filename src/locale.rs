@@ -0,0 +1,690 @@
+use serde_json::Value;
+
+use crate::datetime::{self, DateTime, ENGLISH, Names, TimeZone};
+use crate::expression_functions::format_grouped;
+use crate::i18n;
+use crate::model::AdaptiveCardInvocation;
+
+/// Locale and display time zone used by the formatting functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Locale {
+    pub tag: String,
+    /// Zone name from the session (`session.timezone`) or envelope; `None` keeps each
+    /// timestamp's own offset.
+    pub time_zone: Option<String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            tag: i18n::DEFAULT_LOCALE.to_string(),
+            time_zone: None,
+        }
+    }
+}
+
+/// Formatting conventions of one locale.
+struct LocaleData {
+    tag: &'static str,
+    names: Names,
+    /// `short`, `medium`, `long` and `full` date patterns.
+    date: [&'static str; 4],
+    /// `short` and `medium` time patterns.
+    time: [&'static str; 2],
+    group: &'static str,
+    decimal: &'static str,
+    /// `#` stands for the number.
+    percent: &'static str,
+    /// `¤` stands for the currency symbol and `#` for the amount; negative amounts are
+    /// prefixed with `-`.
+    currency: &'static str,
+    /// Singular and plural names of days, hours, minutes and seconds.
+    units: [(&'static str, &'static str); 4],
+    units_short: [&'static str; 4],
+}
+
+const NBSP: &str = "\u{a0}";
+
+const EN: LocaleData = LocaleData {
+    tag: "en",
+    names: ENGLISH,
+    date: [
+        "M/d/yyyy",
+        "MMM d, yyyy",
+        "MMMM d, yyyy",
+        "dddd, MMMM d, yyyy",
+    ],
+    time: ["h:mm tt", "h:mm:ss tt"],
+    group: ",",
+    decimal: ".",
+    percent: "#%",
+    currency: "¤#",
+    units: [
+        ("day", "days"),
+        ("hour", "hours"),
+        ("minute", "minutes"),
+        ("second", "seconds"),
+    ],
+    units_short: ["d", "h", "min", "s"],
+};
+
+const LOCALES: &[LocaleData] = &[
+    EN,
+    LocaleData {
+        tag: "en-GB",
+        date: [
+            "dd/MM/yyyy",
+            "d MMM yyyy",
+            "d MMMM yyyy",
+            "dddd d MMMM yyyy",
+        ],
+        time: ["HH:mm", "HH:mm:ss"],
+        ..EN
+    },
+    LocaleData {
+        tag: "nl",
+        names: Names {
+            months: [
+                "januari",
+                "februari",
+                "maart",
+                "april",
+                "mei",
+                "juni",
+                "juli",
+                "augustus",
+                "september",
+                "oktober",
+                "november",
+                "december",
+            ],
+            months_short: [
+                "jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec",
+            ],
+            days: [
+                "zondag",
+                "maandag",
+                "dinsdag",
+                "woensdag",
+                "donderdag",
+                "vrijdag",
+                "zaterdag",
+            ],
+            days_short: ["zo", "ma", "di", "wo", "do", "vr", "za"],
+        },
+        date: ["d-M-yyyy", "d MMM yyyy", "d MMMM yyyy", "dddd d MMMM yyyy"],
+        time: ["HH:mm", "HH:mm:ss"],
+        group: ".",
+        decimal: ",",
+        percent: "#%",
+        currency: "¤\u{a0}#",
+        units: [
+            ("dag", "dagen"),
+            ("uur", "uur"),
+            ("minuut", "minuten"),
+            ("seconde", "seconden"),
+        ],
+        units_short: ["d", "u", "min", "s"],
+    },
+    LocaleData {
+        tag: "de",
+        names: Names {
+            months: [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            months_short: [
+                "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.",
+                "Nov.", "Dez.",
+            ],
+            days: [
+                "Sonntag",
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+            ],
+            days_short: ["So.", "Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa."],
+        },
+        date: [
+            "dd.MM.yyyy",
+            "dd.MM.yyyy",
+            "d. MMMM yyyy",
+            "dddd, d. MMMM yyyy",
+        ],
+        time: ["HH:mm", "HH:mm:ss"],
+        group: ".",
+        decimal: ",",
+        percent: "#\u{a0}%",
+        currency: "#\u{a0}¤",
+        units: [
+            ("Tag", "Tage"),
+            ("Stunde", "Stunden"),
+            ("Minute", "Minuten"),
+            ("Sekunde", "Sekunden"),
+        ],
+        units_short: ["T", "Std.", "Min.", "Sek."],
+    },
+    LocaleData {
+        tag: "fr",
+        names: Names {
+            months: [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            months_short: [
+                "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+                "nov.", "déc.",
+            ],
+            days: [
+                "dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi",
+            ],
+            days_short: ["dim.", "lun.", "mar.", "mer.", "jeu.", "ven.", "sam."],
+        },
+        date: [
+            "dd/MM/yyyy",
+            "d MMM yyyy",
+            "d MMMM yyyy",
+            "dddd d MMMM yyyy",
+        ],
+        time: ["HH:mm", "HH:mm:ss"],
+        group: "\u{202f}",
+        decimal: ",",
+        percent: "#\u{a0}%",
+        currency: "#\u{a0}¤",
+        units: [
+            ("jour", "jours"),
+            ("heure", "heures"),
+            ("minute", "minutes"),
+            ("seconde", "secondes"),
+        ],
+        units_short: ["j", "h", "min", "s"],
+    },
+    LocaleData {
+        tag: "es",
+        names: Names {
+            months: [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+            months_short: [
+                "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic",
+            ],
+            days: [
+                "domingo",
+                "lunes",
+                "martes",
+                "miércoles",
+                "jueves",
+                "viernes",
+                "sábado",
+            ],
+            days_short: ["dom", "lun", "mar", "mié", "jue", "vie", "sáb"],
+        },
+        date: [
+            "d/M/yyyy",
+            "d MMM yyyy",
+            "d 'de' MMMM 'de' yyyy",
+            "dddd, d 'de' MMMM 'de' yyyy",
+        ],
+        time: ["H:mm", "H:mm:ss"],
+        group: ".",
+        decimal: ",",
+        percent: "#\u{a0}%",
+        currency: "#\u{a0}¤",
+        units: [
+            ("día", "días"),
+            ("hora", "horas"),
+            ("minuto", "minutos"),
+            ("segundo", "segundos"),
+        ],
+        units_short: ["d", "h", "min", "s"],
+    },
+    LocaleData {
+        tag: "it",
+        names: Names {
+            months: [
+                "gennaio",
+                "febbraio",
+                "marzo",
+                "aprile",
+                "maggio",
+                "giugno",
+                "luglio",
+                "agosto",
+                "settembre",
+                "ottobre",
+                "novembre",
+                "dicembre",
+            ],
+            months_short: [
+                "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+            ],
+            days: [
+                "domenica",
+                "lunedì",
+                "martedì",
+                "mercoledì",
+                "giovedì",
+                "venerdì",
+                "sabato",
+            ],
+            days_short: ["dom", "lun", "mar", "mer", "gio", "ven", "sab"],
+        },
+        date: [
+            "dd/MM/yyyy",
+            "d MMM yyyy",
+            "d MMMM yyyy",
+            "dddd d MMMM yyyy",
+        ],
+        time: ["HH:mm", "HH:mm:ss"],
+        group: ".",
+        decimal: ",",
+        percent: "#%",
+        currency: "#\u{a0}¤",
+        units: [
+            ("giorno", "giorni"),
+            ("ora", "ore"),
+            ("minuto", "minuti"),
+            ("secondo", "secondi"),
+        ],
+        units_short: ["g", "h", "min", "s"],
+    },
+    LocaleData {
+        tag: "pt",
+        names: Names {
+            months: [
+                "janeiro",
+                "fevereiro",
+                "março",
+                "abril",
+                "maio",
+                "junho",
+                "julho",
+                "agosto",
+                "setembro",
+                "outubro",
+                "novembro",
+                "dezembro",
+            ],
+            months_short: [
+                "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+            ],
+            days: [
+                "domingo",
+                "segunda-feira",
+                "terça-feira",
+                "quarta-feira",
+                "quinta-feira",
+                "sexta-feira",
+                "sábado",
+            ],
+            days_short: ["dom", "seg", "ter", "qua", "qui", "sex", "sáb"],
+        },
+        date: [
+            "dd/MM/yyyy",
+            "d 'de' MMM 'de' yyyy",
+            "d 'de' MMMM 'de' yyyy",
+            "dddd, d 'de' MMMM 'de' yyyy",
+        ],
+        time: ["HH:mm", "HH:mm:ss"],
+        group: ".",
+        decimal: ",",
+        percent: "#%",
+        currency: "¤\u{a0}#",
+        units: [
+            ("dia", "dias"),
+            ("hora", "horas"),
+            ("minuto", "minutos"),
+            ("segundo", "segundos"),
+        ],
+        units_short: ["d", "h", "min", "s"],
+    },
+];
+
+impl Locale {
+    /// The first locale of the invocation's fallback chain and the session's time zone
+    /// (`session.timezone`, else the envelope's `timezone` attribute).
+    pub(crate) fn from_invocation(inv: &AdaptiveCardInvocation) -> Self {
+        let requested = i18n::requested_locale(inv);
+        let default = inv
+            .card_spec
+            .default_locale
+            .as_deref()
+            .unwrap_or(i18n::DEFAULT_LOCALE);
+        let tag = i18n::locale_chain(requested.as_deref(), default)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| i18n::DEFAULT_LOCALE.to_string());
+        let time_zone = inv
+            .session
+            .get("timezone")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| {
+                inv.envelope
+                    .as_ref()
+                    .and_then(|envelope| envelope.ctx.attributes.get("timezone").cloned())
+            });
+        Locale { tag, time_zone }
+    }
+
+    /// The same time zone with another locale tag.
+    pub(crate) fn with_tag(&self, tag: &str) -> Locale {
+        Locale {
+            tag: tag.to_string(),
+            time_zone: self.time_zone.clone(),
+        }
+    }
+
+    /// Data for the exact tag, else its language, else English.
+    fn data(&self) -> &'static LocaleData {
+        let tag = self.tag.replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+        LOCALES
+            .iter()
+            .find(|data| data.tag.eq_ignore_ascii_case(&tag))
+            .or_else(|| {
+                LOCALES
+                    .iter()
+                    .find(|data| data.tag.eq_ignore_ascii_case(language))
+            })
+            .unwrap_or(&LOCALES[0])
+    }
+
+    /// Moves `dt` into the session time zone, when one is set.
+    pub(crate) fn localize(&self, dt: DateTime) -> Result<DateTime, String> {
+        match &self.time_zone {
+            Some(name) => Ok(time_zone(name)?.localize(dt)),
+            None => Ok(dt),
+        }
+    }
+
+    /// A .NET-style pattern with this locale's month and day names.
+    pub(crate) fn format_pattern(&self, dt: &DateTime, pattern: &str) -> String {
+        datetime::format_with_names(dt, pattern, &self.data().names)
+    }
+
+    /// `short`, `medium`, `long` or `full` date, or a custom pattern, in the session zone.
+    pub(crate) fn format_date(&self, dt: DateTime, style: &str) -> Result<String, String> {
+        let data = self.data();
+        let pattern = match style {
+            "short" => data.date[0],
+            "medium" => data.date[1],
+            "long" => data.date[2],
+            "full" => data.date[3],
+            custom => custom,
+        };
+        Ok(self.format_pattern(&self.localize(dt)?, pattern))
+    }
+
+    /// `short` or `medium` time, or a custom pattern, in the session zone.
+    pub(crate) fn format_time(&self, dt: DateTime, style: &str) -> Result<String, String> {
+        let data = self.data();
+        let pattern = match style {
+            "short" => data.time[0],
+            "medium" => data.time[1],
+            custom => custom,
+        };
+        Ok(self.format_pattern(&self.localize(dt)?, pattern))
+    }
+
+    pub(crate) fn format_number(&self, value: f64, precision: usize) -> String {
+        let data = self.data();
+        format_grouped(value, precision, data.group, data.decimal)
+    }
+
+    /// `0.25` → `25%` (`25 %` in some locales).
+    pub(crate) fn format_percent(&self, value: f64, precision: usize) -> String {
+        let number = self.format_number(value * 100.0, precision);
+        self.data().percent.replace('#', &number)
+    }
+
+    /// Amount with the ISO 4217 code's symbol; precision defaults to the currency's minor
+    /// units.
+    pub(crate) fn format_currency(
+        &self,
+        value: f64,
+        code: &str,
+        precision: Option<usize>,
+    ) -> String {
+        let code = code.to_ascii_uppercase();
+        let (symbol, minor) = match code.as_str() {
+            "EUR" => ("€", 2),
+            "USD" => ("$", 2),
+            "GBP" => ("£", 2),
+            "JPY" => ("¥", 0),
+            "CNY" => ("CN¥", 2),
+            "INR" => ("₹", 2),
+            "KRW" => ("₩", 0),
+            "CHF" => ("CHF", 2),
+            _ => (code.as_str(), 2),
+        };
+        let number = self.format_number(value.abs(), precision.unwrap_or(minor));
+        let text = self
+            .data()
+            .currency
+            .replace('#', &number)
+            .replace('¤', symbol);
+        if value < 0.0 && number.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            format!("-{text}")
+        } else {
+            text
+        }
+    }
+
+    /// `5400000` ms → `1 hour 30 minutes` (long) or `1 h 30 min` (short).
+    pub(crate) fn format_duration(&self, millis: i64, short: bool) -> String {
+        let data = self.data();
+        let mut remaining = millis.unsigned_abs() / 1000;
+        let mut parts = Vec::new();
+        for (index, size) in [86_400, 3_600, 60, 1].into_iter().enumerate() {
+            let amount = remaining / size;
+            remaining %= size;
+            if amount == 0 && !(index == 3 && parts.is_empty()) {
+                continue;
+            }
+            let unit = if short {
+                data.units_short[index]
+            } else if i18n::plural_category(&self.tag, amount as f64) == "one" {
+                data.units[index].0
+            } else {
+                data.units[index].1
+            };
+            parts.push(format!("{amount}{NBSP}{unit}"));
+        }
+        let text = parts.join(" ");
+        if millis < 0 { format!("-{text}") } else { text }
+    }
+}
+
+pub(crate) fn time_zone(name: &str) -> Result<TimeZone, String> {
+    TimeZone::parse(name).ok_or_else(|| format!("unknown time zone \"{name}\""))
+}
+
+/// Milliseconds from a number of milliseconds or an ISO 8601 duration (`P1DT2H30M`,
+/// `PT45S`; years and months are not supported).
+pub(crate) fn parse_duration(value: &Value) -> Option<i64> {
+    if let Some(millis) = value.as_f64() {
+        return Some(millis as i64);
+    }
+    let raw = value.as_str()?.trim();
+    let (negative, raw) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw),
+    };
+    let body = raw.strip_prefix(['P', 'p'])?;
+    let mut total = 0.0;
+    let mut in_time = false;
+    let mut number = String::new();
+    for c in body.chars() {
+        match c.to_ascii_uppercase() {
+            'T' if number.is_empty() => in_time = true,
+            '0'..='9' | '.' | ',' => number.push(if c == ',' { '.' } else { c }),
+            unit => {
+                let amount: f64 = number.parse().ok()?;
+                number.clear();
+                total += amount
+                    * match (unit, in_time) {
+                        ('W', false) => 604_800_000.0,
+                        ('D', false) => 86_400_000.0,
+                        ('H', true) => 3_600_000.0,
+                        ('M', true) => 60_000.0,
+                        ('S', true) => 1000.0,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    let millis = total.round() as i64;
+    Some(if negative { -millis } else { millis })
+}
+
+/// Expands the Adaptive Card `{{DATE(timestamp[, COMPACT|SHORT|LONG])}}` and
+/// `{{TIME(timestamp)}}` text functions. `COMPACT` uses the `short` date style, `SHORT`
+/// `medium` and `LONG` `full`. Macros with an unparseable timestamp are left for the client.
+pub(crate) fn expand_card_macros(text: &str, locale: &Locale) -> Option<String> {
+    if !has_card_macros(text) {
+        return None;
+    }
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let expanded = candidate.find("}}").and_then(|end| {
+            let inner = &candidate[2..end];
+            let (function, args) = inner.split_once('(')?;
+            let args = args.strip_suffix(')')?;
+            let mut args = args.split(',').map(str::trim);
+            let dt = datetime::parse(args.next()?)?;
+            let text = match (function.trim(), args.next()) {
+                ("DATE", style) => {
+                    let style = match style.unwrap_or("COMPACT") {
+                        "COMPACT" => "short",
+                        "SHORT" => "medium",
+                        "LONG" => "full",
+                        _ => return None,
+                    };
+                    locale.format_date(dt, style).ok()?
+                }
+                ("TIME", None) => locale.format_time(dt, "short").ok()?,
+                _ => return None,
+            };
+            Some((text, end + 2))
+        });
+        match expanded {
+            Some((text, consumed)) => {
+                out.push_str(&text);
+                rest = &candidate[consumed..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = &candidate[2..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+pub(crate) fn has_card_macros(text: &str) -> bool {
+    text.contains("{{DATE(") || text.contains("{{TIME(")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(tag: &str, zone: Option<&str>) -> Locale {
+        Locale {
+            tag: tag.to_string(),
+            time_zone: zone.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn formats_dates_in_locale_and_session_zone() {
+        let dt = datetime::parse("2024-03-31T00:30:00Z").unwrap();
+        let nl = locale("nl-BE", Some("Europe/Brussels"));
+        assert_eq!(nl.format_date(dt, "full").unwrap(), "zondag 31 maart 2024");
+        assert_eq!(nl.format_time(dt, "short").unwrap(), "01:30");
+        let later = dt.add_millis(2 * 3_600_000).unwrap();
+        assert_eq!(nl.format_time(later, "short").unwrap(), "04:30");
+        let us = locale("en-US", Some("America/New_York"));
+        assert_eq!(us.format_date(dt, "medium").unwrap(), "Mar 30, 2024");
+        assert_eq!(us.format_time(dt, "short").unwrap(), "8:30 PM");
+        assert!(
+            locale("en", Some("Mars/Olympus"))
+                .format_date(dt, "short")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn formats_numbers_percentages_currency_and_durations() {
+        let de = locale("de-DE", None);
+        assert_eq!(de.format_number(1234567.891, 2), "1.234.567,89");
+        assert_eq!(de.format_percent(0.256, 1), "25,6\u{a0}%");
+        assert_eq!(de.format_currency(-1234.5, "eur", None), "-1.234,50\u{a0}€");
+        let en = locale("en", None);
+        assert_eq!(en.format_currency(1234.5, "USD", None), "$1,234.50");
+        assert_eq!(en.format_currency(-1234.6, "JPY", None), "-¥1,235");
+        let ms = parse_duration(&Value::String("PT1H30M".into())).unwrap();
+        assert_eq!(en.format_duration(ms, false), "1\u{a0}hour 30\u{a0}minutes");
+        assert_eq!(
+            locale("nl", None).format_duration(ms, true),
+            "1\u{a0}u 30\u{a0}min"
+        );
+        assert_eq!(en.format_duration(0, false), "0\u{a0}seconds");
+    }
+
+    #[test]
+    fn expands_adaptive_card_date_macros() {
+        let en = locale("en", None);
+        let text = "Due {{DATE(2017-02-14T06:08:39Z, SHORT)}} at {{TIME(2017-02-14T06:08:39Z)}}";
+        assert_eq!(
+            expand_card_macros(text, &en).unwrap(),
+            "Due Feb 14, 2017 at 6:08 AM"
+        );
+        assert_eq!(
+            expand_card_macros("{{DATE(soon)}} {{DATE(2017-02-14T06:08:39Z)}}", &en).unwrap(),
+            "{{DATE(soon)}} 2/14/2017"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use greentic_types::InvocationEnvelope;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -18,6 +20,17 @@ pub struct CardSpec {
     pub catalog_name: Option<String>,
     pub template_params: Option<Value>,
     pub asset_registry: Option<std::collections::BTreeMap<String, String>>,
+    /// Expression engine used for `${...}` bindings (`simple`, `adaptive`, or a name
+    /// registered by the host). Defaults to the host default, then `simple`.
+    #[serde(default)]
+    pub expression_engine: Option<String>,
+    /// Base name of the `<name>.<locale>.json` string tables. Defaults to the catalog name,
+    /// or the asset path without its `.json` extension.
+    #[serde(default)]
+    pub strings: Option<String>,
+    /// Last locale of the fallback chain; defaults to `en`.
+    #[serde(default)]
+    pub default_locale: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -27,6 +40,8 @@ pub enum InvocationMode {
     Validate,
     #[default]
     RenderAndValidate,
+    /// Render, apply the fix suggestions of validation issues, and return the repaired card.
+    Fix,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -38,6 +53,71 @@ pub enum ValidationMode {
     Error,
 }
 
+/// Data root searched for bare (unqualified) binding paths.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BindingRoot {
+    Payload,
+    Session,
+    State,
+    Params,
+}
+
+/// How Handlebars output replaces the template string it was rendered from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HandlebarsMode {
+    /// Always write back the rendered text.
+    #[default]
+    Text,
+    /// A string consisting solely of one expression (`"{{payload.count}}"`) becomes the
+    /// expression's JSON value, and `"{{{json x}}}"` splices `x` in as JSON.
+    Typed,
+}
+
+/// Escaping applied to `payload`/`session` (and `$data`) strings bound into markdown
+/// text (`TextBlock.text`, `Fact.title`/`Fact.value`).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EscapePolicy {
+    /// Insert values verbatim.
+    #[default]
+    None,
+    /// Backslash-escape markdown emphasis, link, code and list syntax.
+    Markdown,
+    /// Markdown escaping plus HTML entities for `&`, `<`, `>`, with line breaks and other
+    /// control characters replaced by spaces.
+    Strict,
+}
+
+/// Channel the card is delivered to; validation then reports what that host cannot render.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HostProfile {
+    Teams,
+    Webex,
+    /// Outlook Actionable Messages.
+    Outlook,
+    /// Bot Framework Web Chat.
+    WebChat,
+}
+
+/// What to do when a `${...}`/`@{...}` binding does not resolve.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingPolicy {
+    /// Abort the render with a binding error.
+    #[default]
+    Error,
+    /// Substitute an empty string.
+    Empty,
+    /// Leave the original placeholder text in place.
+    #[serde(alias = "keepPlaceholder")]
+    KeepPlaceholder,
+    /// Substitute JSON `null` (an empty string inside larger text).
+    Null,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AdaptiveCardInvocation {
@@ -69,6 +149,74 @@ pub struct AdaptiveCardInvocation {
     #[serde(alias = "validation_mode")]
     pub validation_mode: ValidationMode,
 
+    /// Handling of unresolved bindings; anything but `Error` reports them as
+    /// `missing-binding` validation issues instead of failing the render.
+    #[serde(default)]
+    #[serde(alias = "binding_policy")]
+    pub binding_policy: BindingPolicy,
+
+    /// Report per-binding provenance in `AdaptiveCardResult::binding_diagnostics`.
+    #[serde(default)]
+    #[serde(alias = "binding_diagnostics")]
+    pub binding_diagnostics: bool,
+
+    /// Refuse bare paths such as `${name}` unless the current `$data` item resolves them.
+    #[serde(default)]
+    #[serde(alias = "strict_paths")]
+    pub strict_paths: bool,
+
+    /// Order in which bare paths are looked up; roots left out are not searched. Empty
+    /// means payload, session, state, params.
+    #[serde(default)]
+    #[serde(alias = "root_precedence")]
+    pub root_precedence: Vec<BindingRoot>,
+
+    /// How strings rendered through Handlebars are written back into the card.
+    #[serde(default)]
+    #[serde(alias = "handlebars_mode")]
+    pub handlebars_mode: HandlebarsMode,
+
+    /// Escaping of untrusted bound values; a binding prefixed with `raw:` (`${raw:x}`,
+    /// `@{raw:x}`) opts out.
+    #[serde(default)]
+    #[serde(alias = "escape_policy")]
+    pub escape_policy: EscapePolicy,
+
+    /// Target host whose capabilities the rendered card is validated against.
+    #[serde(default)]
+    #[serde(alias = "host_profile")]
+    pub host_profile: Option<HostProfile>,
+
+    /// Rewrite the rendered card for `target_version` and/or `host_profile` (applying
+    /// fallbacks, replacing unsupported actions and lowering `version`) before validation.
+    #[serde(default)]
+    pub downgrade: bool,
+
+    /// Highest Adaptive Card version the downgraded card may use, e.g. `"1.2"`; defaults
+    /// to the host profile's maximum.
+    #[serde(default)]
+    #[serde(alias = "target_version")]
+    pub target_version: Option<String>,
+
+    /// Per-rule levels keyed by issue code (e.g. `"missing-title": "off"`), overriding the
+    /// default severities from `validation_rules()`.
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleLevel>,
+
+    /// Largest serialized rendered card, in bytes; defaults to the host profile's limit.
+    #[serde(default)]
+    #[serde(alias = "max_payload_bytes")]
+    pub max_payload_bytes: Option<usize>,
+
+    /// Drop nulls and default-valued properties from the rendered card before measuring.
+    #[serde(default)]
+    pub minify: bool,
+
+    /// Hosts that card URLs may or may not point to.
+    #[serde(default)]
+    #[serde(alias = "url_policy")]
+    pub url_policy: UrlPolicy,
+
     /// Optional shared invocation envelope metadata from the host.
     #[serde(default)]
     pub envelope: Option<InvocationEnvelope>,
@@ -159,6 +307,9 @@ pub enum SessionUpdateOp {
 #[serde(rename_all = "camelCase")]
 pub struct CardFeatureSummary {
     pub version: Option<String>,
+    /// Lowest Adaptive Card version supporting every element, action and property used.
+    #[serde(default)]
+    pub minimum_version: Option<String>,
     pub used_elements: Vec<String>,
     pub used_actions: Vec<String>,
     pub uses_show_card: bool,
@@ -167,6 +318,21 @@ pub struct CardFeatureSummary {
     pub uses_auth: bool,
     #[serde(default)]
     pub requires_features: Value,
+    /// Element types dropped from the rendered card by a falsy `$when`.
+    #[serde(default)]
+    pub removed_elements: Vec<String>,
+}
+
+/// A change made to the rendered card by the downgrade pass.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CardTransformation {
+    /// `fallback-applied`, `element-dropped`, `action-rewritten`, `property-removed` or
+    /// `version-lowered`.
+    pub code: String,
+    pub message: String,
+    /// JSON pointer of the affected node in the card before downgrading.
+    pub path: String,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
@@ -175,6 +341,91 @@ pub struct ValidationIssue {
     pub code: String,
     pub message: String,
     pub path: String,
+    #[serde(default)]
+    pub severity: Severity,
+    /// JSON Patch operations that repair the issue, when there is an obvious fix.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fix: Vec<PatchOperation>,
+}
+
+/// One RFC 6902 JSON Patch operation against the rendered card.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PatchOperation {
+    pub op: PatchOp,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// How serious a validation issue is; `ValidationMode::Error` only fails on `Error`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+    Info,
+}
+
+/// Host allowlist and denylist for URLs in the rendered card. Entries match the host and
+/// its subdomains; an empty allowlist allows every host not denied.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlPolicy {
+    #[serde(default)]
+    #[serde(alias = "allowed_hosts")]
+    pub allowed_hosts: Vec<String>,
+    #[serde(default)]
+    #[serde(alias = "denied_hosts")]
+    pub denied_hosts: Vec<String>,
+}
+
+/// Configured level of a validation rule: a severity, or `Off` to suppress it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleLevel {
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+/// Provenance of a single `${...}`/`@{...}` binding or `$when`/`$data` expression.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingDiagnostic {
+    /// JSON pointer of the bound value in the rendered card.
+    pub pointer: String,
+    /// Expression or path as written in the template, without the `${`/`@{` markers.
+    pub expression: String,
+    /// Roots the expression's paths resolved from (`payload`, `session`, `state`, `params`,
+    /// `$data`, `$index`, or `default` for a `||` fallback), in lookup order.
+    #[serde(default)]
+    pub roots: Vec<String>,
+    /// JSON type of the result, or `missing` when the binding did not resolve.
+    pub value_type: String,
+}
+
+/// Opt-in binding report: the binding counters plus one entry per evaluated binding.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BindingDiagnostics {
+    pub handlebars_expansions: u64,
+    pub placeholder_replacements: u64,
+    pub expression_evaluations: u64,
+    pub missing_paths: u64,
+    pub repeated_elements: u64,
+    pub removed_elements: u64,
+    #[serde(default)]
+    pub bindings: Vec<BindingDiagnostic>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
@@ -199,4 +450,38 @@ pub struct AdaptiveCardResult {
     pub validation_issues: Vec<ValidationIssue>,
     #[serde(default)]
     pub telemetry_events: Vec<TelemetryEvent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binding_diagnostics: Option<BindingDiagnostics>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transformations: Vec<CardTransformation>,
+    /// Issues repaired in `Fix` mode, in the order their fixes were applied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied_fixes: Vec<ValidationIssue>,
+    #[serde(default)]
+    pub payload_size: PayloadSize,
+}
+
+/// Serialized (compact JSON) size of the rendered card.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadSize {
+    pub bytes: usize,
+    /// `max_payload_bytes`, or the host profile's limit.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Size before `minify`, when it ran.
+    #[serde(default)]
+    pub unminified_bytes: Option<usize>,
+    /// Largest elements and actions, biggest first.
+    #[serde(default)]
+    pub largest: Vec<SizeContribution>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeContribution {
+    /// JSON pointer of the element or action.
+    pub path: String,
+    pub kind: String,
+    pub bytes: usize,
 }
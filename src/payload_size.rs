@@ -0,0 +1,214 @@
+use serde_json::{Map, Value};
+
+use crate::compatibility::escape_pointer;
+use crate::host_profile::HostCapabilities;
+use crate::model::{PayloadSize, SizeContribution, ValidationIssue};
+
+/// How many of the largest elements and actions the size report lists.
+const LARGEST_COUNT: usize = 5;
+
+/// Property values that are the renderer default on every element, input and action
+/// that has them, so removing them does not change the card.
+const BOOLEAN_DEFAULTS: &[(&str, bool)] = &[
+    ("isVisible", true),
+    ("separator", false),
+    ("wrap", false),
+    ("isSubtle", false),
+    ("isRequired", false),
+    ("isMultiSelect", false),
+    ("italic", false),
+    ("strikethrough", false),
+    ("underline", false),
+    ("highlight", false),
+];
+const STRING_DEFAULTS: &[(&str, &str)] = &[
+    ("spacing", "default"),
+    ("size", "default"),
+    ("weight", "default"),
+    ("color", "default"),
+    ("fontType", "default"),
+    ("height", "auto"),
+];
+
+/// Removes `null` properties and default-valued properties of typed nodes. Submit `data`
+/// is left untouched, since the host receives it verbatim.
+pub(crate) fn minify(card: &mut Value) {
+    if let Some(map) = card.as_object_mut() {
+        minify_object(map);
+    }
+}
+
+fn minify_object(map: &mut Map<String, Value>) {
+    let typed = map.contains_key("type");
+    map.retain(|key, value| !(value.is_null() || (typed && is_default(key, value))));
+    for (key, value) in map.iter_mut() {
+        if key != "data" {
+            minify_value(value);
+        }
+    }
+}
+
+fn is_default(key: &str, value: &Value) -> bool {
+    match value {
+        Value::Bool(flag) => BOOLEAN_DEFAULTS.contains(&(key, *flag)),
+        Value::String(text) => STRING_DEFAULTS
+            .iter()
+            .any(|(name, default)| *name == key && text.eq_ignore_ascii_case(default)),
+        _ => false,
+    }
+}
+
+fn minify_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => minify_object(map),
+        Value::Array(items) => items.iter_mut().for_each(minify_value),
+        _ => {}
+    }
+}
+
+/// Size of the card as compact JSON, with its largest elements and actions.
+pub(crate) fn measure(
+    card: &Value,
+    limit: Option<usize>,
+    unminified_bytes: Option<usize>,
+) -> PayloadSize {
+    let mut largest = Vec::new();
+    if let Some(map) = card.as_object() {
+        for (key, child) in map {
+            collect(child, format!("/{}", escape_pointer(key)), &mut largest);
+        }
+    }
+    largest.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    largest.truncate(LARGEST_COUNT);
+    PayloadSize {
+        bytes: serialized_len(card),
+        limit,
+        unminified_bytes,
+        largest,
+    }
+}
+
+pub(crate) fn serialized_len(value: &Value) -> usize {
+    serde_json::to_vec(value)
+        .map(|bytes| bytes.len())
+        .unwrap_or(0)
+}
+
+fn collect(value: &Value, path: String, out: &mut Vec<SizeContribution>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(kind) = map.get("type").and_then(Value::as_str) {
+                out.push(SizeContribution {
+                    path: path.clone(),
+                    kind: kind.to_string(),
+                    bytes: serialized_len(value),
+                });
+            }
+            for (key, child) in map {
+                if key != "data" {
+                    collect(child, format!("{path}/{}", escape_pointer(key)), out);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect(item, format!("{path}/{index}"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reports a card over its limit: `host-payload-too-large` when the limit is the host's
+/// (`host` is `Some`), otherwise `payload-too-large`.
+pub(crate) fn validate_size(
+    size: &PayloadSize,
+    host: Option<&HostCapabilities>,
+) -> Option<ValidationIssue> {
+    let limit = size.limit.filter(|limit| size.bytes > *limit)?;
+    let (code, mut message) = match host {
+        Some(host) => (
+            "host-payload-too-large",
+            format!(
+                "The card is {} bytes; {} accepts at most {limit} bytes",
+                size.bytes, host.name
+            ),
+        ),
+        None => (
+            "payload-too-large",
+            format!(
+                "The card is {} bytes, over the {limit} byte limit",
+                size.bytes
+            ),
+        ),
+    };
+    if let Some(top) = size.largest.first() {
+        message.push_str(&format!(
+            "; largest is the `{}` at {} ({} bytes)",
+            top.kind, top.path, top.bytes
+        ));
+    }
+    Some(ValidationIssue {
+        code: code.into(),
+        message,
+        path: "/".into(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn minifies_and_ranks_largest_nodes() {
+        let mut card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.5",
+            "fallbackText": null,
+            "body": [
+                { "type": "TextBlock", "text": "Hi", "wrap": false, "isVisible": true, "color": "accent" },
+                { "type": "Container", "items": [
+                    { "type": "TextBlock", "text": "A much longer paragraph of text", "spacing": "default" }
+                ] }
+            ],
+            "actions": [
+                { "type": "Action.Submit", "title": "Go", "data": { "note": null, "wrap": false } }
+            ]
+        });
+        let before = serialized_len(&card);
+        minify(&mut card);
+        assert_eq!(
+            card,
+            json!({
+                "type": "AdaptiveCard",
+                "version": "1.5",
+                "body": [
+                    { "type": "TextBlock", "text": "Hi", "color": "accent" },
+                    { "type": "Container", "items": [
+                        { "type": "TextBlock", "text": "A much longer paragraph of text" }
+                    ] }
+                ],
+                "actions": [
+                    { "type": "Action.Submit", "title": "Go", "data": { "note": null, "wrap": false } }
+                ]
+            })
+        );
+
+        let size = measure(&card, Some(100), Some(before));
+        assert_eq!(size.bytes, serialized_len(&card));
+        let paths: Vec<&str> = size.largest.iter().map(|node| node.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["/body/1", "/actions/0", "/body/1/items/0", "/body/0"]
+        );
+        let issue = validate_size(&size, None).expect("over the limit");
+        assert_eq!(issue.code, "payload-too-large");
+        assert!(
+            issue
+                .message
+                .contains("largest is the `Container` at /body/1")
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::compatibility::{escape_pointer, implicit_type};
+use crate::model::ValidationIssue;
+
+/// Keys whose values hold elements or actions. `data` and other free-form objects are not
+/// searched, so ids inside submit payloads are never mistaken for element ids.
+const CONTAINER_KEYS: &[&str] = &[
+    "body",
+    "items",
+    "actions",
+    "columns",
+    "card",
+    "selectAction",
+    "inlineAction",
+    "images",
+    "rows",
+    "cells",
+    "inlines",
+    "pages",
+    "refresh",
+    "action",
+    "fallback",
+];
+
+/// Input properties rendered for, and announced with, the input carrying them.
+const INPUT_ASSOCIATIONS: &[&str] = &["label", "errorMessage"];
+
+/// An `id` declared by an element, input or action.
+struct Declaration {
+    kind: String,
+    path: String,
+    /// Declared inside a `fallback`, which only renders in place of its primary node.
+    in_fallback: bool,
+}
+
+/// An id referenced by another node.
+struct Reference {
+    id: String,
+    path: String,
+    expects: Expected,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Expected {
+    /// `Action.ToggleVisibility.targetElements`: any element other than an input.
+    Element,
+    /// `associatedInputs` lists.
+    Input,
+}
+
+/// An input `label` or `errorMessage`, associated with its input through the input's id.
+struct Association {
+    /// `None` when the id is not a non-empty string.
+    id: Option<String>,
+    path: String,
+}
+
+/// Checks id cross-references: every id is declared once across the whole card (nested
+/// `Action.ShowCard` cards included), toggle targets (including those of `selectAction`s)
+/// name existing non-input elements, `associatedInputs` lists name existing inputs, and an
+/// input's `label`/`errorMessage` can be tied to it by a usable, unique id.
+pub(crate) fn validate_references(card: &Value) -> Vec<ValidationIssue> {
+    let mut found = Found::default();
+    if let Some(map) = card.as_object() {
+        collect(map, "AdaptiveCard", "", false, &mut found);
+    }
+    let Found {
+        ids,
+        references,
+        associations,
+    } = found;
+
+    let mut issues = Vec::new();
+    for (id, declarations) in &ids {
+        let primary: Vec<&Declaration> = declarations.iter().filter(|d| !d.in_fallback).collect();
+        for duplicate in primary.iter().skip(1) {
+            // Input/input and action/action clashes keep their historical codes.
+            let both = |prefix: &str| {
+                primary[0].kind.starts_with(prefix) && duplicate.kind.starts_with(prefix)
+            };
+            if both("Input.") || both("Action.") {
+                continue;
+            }
+            issues.push(ValidationIssue {
+                code: "duplicate-element-id".into(),
+                message: format!(
+                    "id `{id}` is already used by the {} at {}",
+                    primary[0].kind, primary[0].path
+                ),
+                path: duplicate.path.clone(),
+                ..Default::default()
+            });
+        }
+    }
+
+    for reference in references {
+        // A fallback only renders in place of its element, so a declaration outside any
+        // fallback is the one the reference reaches.
+        let target = ids.get(&reference.id).and_then(|declarations| {
+            declarations
+                .iter()
+                .find(|d| !d.in_fallback)
+                .or(declarations.first())
+        });
+        let Some(target) = target else {
+            issues.push(ValidationIssue {
+                code: "unknown-id-reference".into(),
+                message: format!("no element in the card has id `{}`", reference.id),
+                path: reference.path,
+                ..Default::default()
+            });
+            continue;
+        };
+        let input = target.kind.starts_with("Input.");
+        let action = target.kind.starts_with("Action.");
+        match reference.expects {
+            Expected::Element if input || action => issues.push(ValidationIssue {
+                code: "invalid-toggle-target".into(),
+                message: format!(
+                    "Action.ToggleVisibility targets `{}`, which is an {} rather than an element",
+                    reference.id, target.kind
+                ),
+                path: reference.path,
+                ..Default::default()
+            }),
+            Expected::Input if !input => issues.push(ValidationIssue {
+                code: "invalid-input-reference".into(),
+                message: format!(
+                    "`{}` refers to a {}, not an input",
+                    reference.id, target.kind
+                ),
+                path: reference.path,
+                ..Default::default()
+            }),
+            _ => {}
+        }
+    }
+
+    for association in associations {
+        let message = match &association.id {
+            None => "the input has no usable id to associate it with".to_string(),
+            Some(id) => {
+                let declared = ids
+                    .get(id)
+                    .map_or(0, |d| d.iter().filter(|d| !d.in_fallback).count());
+                if declared < 2 {
+                    continue;
+                }
+                format!("the input's id `{id}` is declared {declared} times, so it is ambiguous")
+            }
+        };
+        issues.push(ValidationIssue {
+            code: "invalid-input-association".into(),
+            message,
+            path: association.path,
+            ..Default::default()
+        });
+    }
+    issues
+}
+
+#[derive(Default)]
+struct Found {
+    ids: BTreeMap<String, Vec<Declaration>>,
+    references: Vec<Reference>,
+    associations: Vec<Association>,
+}
+
+fn collect(map: &Map<String, Value>, kind: &str, path: &str, in_fallback: bool, found: &mut Found) {
+    if kind != "AdaptiveCard"
+        && let Some(id) = map.get("id").and_then(Value::as_str)
+    {
+        found
+            .ids
+            .entry(id.to_string())
+            .or_default()
+            .push(Declaration {
+                kind: kind.to_string(),
+                path: pointer(path),
+                in_fallback,
+            });
+    }
+
+    if let Some(targets) = map.get("targetElements").and_then(Value::as_array) {
+        for (index, target) in targets.iter().enumerate() {
+            let (id, target_path) = match target {
+                Value::String(id) => (id.as_str(), format!("{path}/targetElements/{index}")),
+                Value::Object(entry) => match entry.get("elementId").and_then(Value::as_str) {
+                    Some(id) => (id, format!("{path}/targetElements/{index}/elementId")),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            found.references.push(Reference {
+                id: id.to_string(),
+                path: target_path,
+                expects: Expected::Element,
+            });
+        }
+    }
+    // `associatedInputs` is normally `auto`/`none`; hosts that accept explicit input ids
+    // take a list (or a single id).
+    match map.get("associatedInputs") {
+        Some(Value::String(id))
+            if !id.eq_ignore_ascii_case("auto") && !id.eq_ignore_ascii_case("none") =>
+        {
+            found.references.push(Reference {
+                id: id.clone(),
+                path: format!("{path}/associatedInputs"),
+                expects: Expected::Input,
+            });
+        }
+        Some(Value::Array(list)) => {
+            for (index, id) in list.iter().enumerate() {
+                if let Some(id) = id.as_str() {
+                    found.references.push(Reference {
+                        id: id.to_string(),
+                        path: format!("{path}/associatedInputs/{index}"),
+                        expects: Expected::Input,
+                    });
+                }
+            }
+        }
+        _ => {}
+    }
+    // A missing id is already reported as `missing-id`; an empty or non-string one is not.
+    if kind.starts_with("Input.") && map.contains_key("id") {
+        let id = map
+            .get("id")
+            .and_then(Value::as_str)
+            .filter(|id| !id.is_empty());
+        for property in INPUT_ASSOCIATIONS {
+            if map.contains_key(*property) {
+                found.associations.push(Association {
+                    id: id.map(str::to_string),
+                    path: format!("{path}/{property}"),
+                });
+            }
+        }
+    }
+
+    for key in CONTAINER_KEYS {
+        let Some(child) = map.get(*key) else {
+            continue;
+        };
+        let child_path = format!("{path}/{}", escape_pointer(key));
+        let nested = in_fallback || *key == "fallback";
+        let items: Vec<(String, &Value)> = match child {
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (format!("{child_path}/{index}"), item))
+                .collect(),
+            other => vec![(child_path, other)],
+        };
+        for (item_path, item) in items {
+            let Some(item_map) = item.as_object() else {
+                continue;
+            };
+            let item_kind = item_map
+                .get("type")
+                .and_then(Value::as_str)
+                .or_else(|| implicit_type(kind, key))
+                .unwrap_or("element");
+            collect(item_map, item_kind, &item_path, nested, found);
+        }
+    }
+}
+
+fn pointer(path: &str) -> String {
+    if path.is_empty() {
+        "/".into()
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flags_unknown_and_misdirected_references() {
+        let card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.6",
+            "body": [
+                { "type": "TextBlock", "id": "intro", "text": "Hi" },
+                { "type": "Input.Text", "id": "name" },
+                { "type": "Container", "id": "intro", "items": [] },
+                { "type": "Table", "id": "grid", "fallback": { "type": "TextBlock", "id": "grid", "text": "-" } },
+                { "type": "Image", "url": "a.png", "selectAction": {
+                    "type": "Action.ToggleVisibility", "targetElements": ["ghost"]
+                } },
+                { "type": "Input.Number", "id": "", "label": "Age", "errorMessage": "Required" },
+                { "type": "Carousel", "pages": [ { "type": "CarouselPage", "id": "grid", "items": [] } ] },
+                { "type": "Table", "fallback": { "type": "Input.Text", "id": "later" } },
+                { "type": "TextBlock", "id": "later", "text": "Shown" }
+            ],
+            "actions": [
+                {
+                    "type": "Action.ToggleVisibility",
+                    "id": "name",
+                    "targetElements": ["intro", { "elementId": "name" }, "missing", "later"]
+                },
+                { "type": "Action.Submit", "associatedInputs": ["name", "intro"], "data": { "id": "intro" } }
+            ]
+        });
+        let issues: Vec<(String, String)> = validate_references(&card)
+            .into_iter()
+            .map(|issue| (issue.code, issue.path))
+            .collect();
+        let expected = [
+            ("duplicate-element-id", "/body/6/pages/0"),
+            ("duplicate-element-id", "/body/2"),
+            ("duplicate-element-id", "/actions/0"),
+            (
+                "unknown-id-reference",
+                "/body/4/selectAction/targetElements/0",
+            ),
+            (
+                "invalid-toggle-target",
+                "/actions/0/targetElements/1/elementId",
+            ),
+            ("unknown-id-reference", "/actions/0/targetElements/2"),
+            ("invalid-input-reference", "/actions/1/associatedInputs/1"),
+            ("invalid-input-association", "/body/5/label"),
+            ("invalid-input-association", "/body/5/errorMessage"),
+        ];
+        assert_eq!(
+            issues,
+            expected.map(|(code, path)| (code.to_string(), path.to_string()))
+        );
+    }
+}
@@ -1,15 +1,32 @@
+use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 
 use handlebars::Handlebars;
 use serde_json::{Map, Value};
 
+use crate::accessibility;
 use crate::asset_resolver::resolve_with_host;
+use crate::compatibility;
+use crate::downgrade::{self, DowngradeTarget};
 use crate::error::ComponentError;
-use crate::expression::{ExpressionEngine, SimpleExpressionEngine, stringify_value};
+use crate::expression::{ExpressionEngine, resolve_engine, stringify_value, truthy, type_name};
+use crate::fixes;
+use crate::handlebars_helpers;
+use crate::host_profile;
+use crate::i18n::{self, Translations};
+use crate::locale::{self, Locale};
 use crate::model::{
-    AdaptiveCardInvocation, CardFeatureSummary, CardSource, CardSpec, ValidationIssue,
+    AdaptiveCardInvocation, BindingDiagnostic, BindingDiagnostics, BindingPolicy, BindingRoot,
+    CardFeatureSummary, CardSource, CardSpec, CardTransformation, EscapePolicy, HandlebarsMode,
+    InvocationMode, PayloadSize, ValidationIssue,
 };
+use crate::payload_size;
+use crate::references;
+use crate::rules;
+use crate::template_cache::{self, CompiledCard, TemplateCacheStats};
+use crate::url_safety::{self, NodeMove, UntrustedPointers};
+use crate::validation;
 
 #[derive(Debug, Default, Clone)]
 pub struct BindingSummary {
@@ -17,6 +34,11 @@ pub struct BindingSummary {
     pub placeholder_replacements: u64,
     pub expression_evaluations: u64,
     pub missing_paths: u64,
+    pub repeated_elements: u64,
+    pub removed_elements: u64,
+    pub removed_element_types: BTreeSet<String>,
+    pub expression_engine: String,
+    pub template_cache: Option<TemplateCacheStats>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -33,18 +55,100 @@ pub struct RenderOutcome {
     pub validation_issues: Vec<ValidationIssue>,
     pub asset_resolution: AssetResolution,
     pub binding_summary: BindingSummary,
+    pub binding_diagnostics: Option<BindingDiagnostics>,
+    pub transformations: Vec<CardTransformation>,
+    pub applied_fixes: Vec<ValidationIssue>,
+    pub payload_size: PayloadSize,
 }
 
 pub fn render_card(inv: &AdaptiveCardInvocation) -> Result<RenderOutcome, ComponentError> {
     let mut summary = BindingSummary::default();
     let (mut card, asset_resolution) = resolve_card(inv)?;
-    apply_handlebars(&mut card, inv, &mut summary)?;
-    let ctx = BindingContext::from_invocation(inv);
-    let engine = SimpleExpressionEngine;
-    apply_bindings(&mut card, &ctx, &engine, &mut summary)?;
+    let translations = if uses_translations(&card) {
+        load_translations(inv)?
+    } else {
+        Translations::default()
+    };
+    let mut untrusted = apply_handlebars(
+        &mut card,
+        inv,
+        &translations,
+        asset_resolution.hash.as_deref(),
+        &mut summary,
+    )?;
+    let mut ctx = BindingContext::from_invocation(inv);
+    ctx.translations = translations;
+    let (engine_name, engine) = resolve_engine(inv.card_spec.expression_engine.as_deref())?;
+    summary.expression_engine = engine_name;
+    let mut pass = BindingPass::new(engine.as_ref(), inv.binding_policy.clone(), summary);
+    if inv.binding_diagnostics {
+        pass.diagnostics = Some(Vec::new());
+    }
+    pass.escape_policy = inv.escape_policy.clone();
+    apply_bindings(&mut card, &mut ctx, &mut pass)?;
+    untrusted.relocate(&pass.moves);
+    untrusted.extend(std::mem::take(&mut pass.untrusted));
+    expand_card_macros(&mut card, ctx.locale());
+    let transformations = if inv.downgrade {
+        let mut moves = Vec::new();
+        let log = downgrade::downgrade(&mut card, &downgrade_target(inv)?, &mut moves);
+        untrusted.relocate(&moves);
+        log
+    } else {
+        Vec::new()
+    };
+    let applied_fixes = if inv.mode == InvocationMode::Fix {
+        fixes::repair(&mut card, &inv.rules)
+    } else {
+        Vec::new()
+    };
+    for fixed in &applied_fixes {
+        untrusted.follow_patch(&fixed.fix);
+    }
+    // Minifying only removes scalar properties, so the untrusted pointers stay valid.
+    let unminified_bytes = inv.minify.then(|| {
+        let bytes = payload_size::serialized_len(&card);
+        payload_size::minify(&mut card);
+        bytes
+    });
+    let BindingPass {
+        issues: binding_issues,
+        summary,
+        diagnostics,
+        ..
+    } = pass;
+    let binding_diagnostics = diagnostics.map(|bindings| BindingDiagnostics {
+        handlebars_expansions: summary.handlebars_expansions,
+        placeholder_replacements: summary.placeholder_replacements,
+        expression_evaluations: summary.expression_evaluations,
+        missing_paths: summary.missing_paths,
+        repeated_elements: summary.repeated_elements,
+        removed_elements: summary.removed_elements,
+        bindings,
+    });
 
-    let features = analyze_features(&card);
-    let validation_issues = validate_card(&card);
+    let mut features = analyze_features(&card);
+    features.removed_elements = summary.removed_element_types.iter().cloned().collect();
+    let mut validation_issues = binding_issues;
+    validation_issues.extend(validate_card(&card));
+    let host = inv.host_profile.as_ref().map(host_profile::capabilities);
+    if let Some(host) = host {
+        validation_issues.extend(host_profile::validate_for_host(&card, &features, host));
+    }
+    validation_issues.extend(accessibility::validate_accessibility(&card));
+    validation_issues.extend(url_safety::validate_urls(
+        &card,
+        &inv.url_policy,
+        untrusted.pointers(),
+    ));
+    let limit = inv
+        .max_payload_bytes
+        .or(host.map(|host| host.max_payload_bytes));
+    let payload_size = payload_size::measure(&card, limit, unminified_bytes);
+    let limit_host = host.filter(|_| inv.max_payload_bytes.is_none());
+    validation_issues.extend(payload_size::validate_size(&payload_size, limit_host));
+    let mut validation_issues = rules::apply(validation_issues, &inv.rules);
+    fixes::suggest(&card, &mut validation_issues);
 
     Ok(RenderOutcome {
         card,
@@ -52,9 +156,40 @@ pub fn render_card(inv: &AdaptiveCardInvocation) -> Result<RenderOutcome, Compon
         validation_issues,
         asset_resolution,
         binding_summary: summary,
+        binding_diagnostics,
+        transformations,
+        applied_fixes,
+        payload_size,
     })
 }
 
+/// The version and host a `downgrade` invocation targets: `target_version`, capped by the
+/// host profile's maximum.
+fn downgrade_target(
+    inv: &AdaptiveCardInvocation,
+) -> Result<DowngradeTarget<'static>, ComponentError> {
+    let host = inv.host_profile.as_ref().map(host_profile::capabilities);
+    let requested = match inv.target_version.as_deref() {
+        Some(raw) => Some(compatibility::parse_version(raw).ok_or_else(|| {
+            ComponentError::InvalidInput(format!(
+                "target_version must be an Adaptive Card 1.x version, got \"{raw}\""
+            ))
+        })?),
+        None => None,
+    };
+    let version = match (requested, host) {
+        (Some(requested), Some(host)) => requested.min(host.max_version),
+        (Some(requested), None) => requested,
+        (None, Some(host)) => host.max_version,
+        (None, None) => {
+            return Err(ComponentError::InvalidInput(
+                "downgrade requires target_version or host_profile".into(),
+            ));
+        }
+    };
+    Ok(DowngradeTarget { version, host })
+}
+
 fn resolve_card(inv: &AdaptiveCardInvocation) -> Result<(Value, AssetResolution), ComponentError> {
     match inv.card_source {
         CardSource::Inline => {
@@ -265,16 +400,48 @@ fn load_with_candidates(
     }))
 }
 
+/// Data visible to bindings: payload, session, state, template params and `$data` scopes.
 #[derive(Debug)]
 pub struct BindingContext {
     payload: Value,
     session: Value,
     state: Value,
     template_params: Value,
+    scopes: Vec<DataScope>,
+    /// Roots searched, in order, for bare paths outside a `$data` item.
+    precedence: Vec<BindingRoot>,
+    /// Reject bare paths that are not resolved by the current `$data` item.
+    strict_paths: bool,
+    translations: Translations,
+    locale: Locale,
+    log: RefCell<LookupLog>,
+}
+
+/// Side observations of lookups, drained by the binding pass after each binding.
+#[derive(Debug, Default)]
+struct LookupLog {
+    roots: Vec<&'static str>,
+    /// Whether a lookup read `payload` or `session` data, directly or through `$data`.
+    untrusted: bool,
+    /// Bare paths present in more than one root, with the roots that contain them.
+    ambiguous: Vec<(String, Vec<&'static str>)>,
+    /// Bare paths refused because of `strict_paths`.
+    unqualified: Vec<String>,
+    /// Keys passed to `t()` that no string table defines.
+    missing_translations: Vec<String>,
+}
+
+/// Data context introduced by an Adaptive Card Templating `$data` binding.
+#[derive(Debug)]
+struct DataScope {
+    data: Value,
+    index: Option<usize>,
+    /// Whether `data` was bound from `payload` or `session`.
+    untrusted: bool,
 }
 
 impl BindingContext {
-    fn from_invocation(inv: &AdaptiveCardInvocation) -> Self {
+    pub(crate) fn from_invocation(inv: &AdaptiveCardInvocation) -> Self {
         BindingContext {
             payload: inv.payload.clone(),
             session: inv.session.clone(),
@@ -284,62 +451,150 @@ impl BindingContext {
                 .template_params
                 .clone()
                 .unwrap_or(Value::Object(Map::new())),
+            scopes: Vec::new(),
+            precedence: if inv.root_precedence.is_empty() {
+                vec![
+                    BindingRoot::Payload,
+                    BindingRoot::Session,
+                    BindingRoot::State,
+                    BindingRoot::Params,
+                ]
+            } else {
+                inv.root_precedence.clone()
+            },
+            strict_paths: inv.strict_paths,
+            translations: Translations::default(),
+            locale: Locale::from_invocation(inv),
+            log: RefCell::new(LookupLog::default()),
         }
     }
 
+    /// Locale and time zone for the formatting functions.
+    pub(crate) fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Formats the string-table message for `key`; unknown keys are logged and returned
+    /// as is.
+    pub(crate) fn translate(&self, key: &str, args: &Map<String, Value>) -> String {
+        self.translations.translate(key, args).unwrap_or_else(|| {
+            self.log
+                .borrow_mut()
+                .missing_translations
+                .push(key.to_string());
+            key.to_string()
+        })
+    }
+
+    /// Returns and clears everything logged since the previous call.
+    fn take_log(&self) -> LookupLog {
+        std::mem::take(&mut *self.log.borrow_mut())
+    }
+
+    fn root(&self, root: &BindingRoot) -> (&Value, &'static str) {
+        match root {
+            BindingRoot::Payload => (&self.payload, "payload"),
+            BindingRoot::Session => (&self.session, "session"),
+            BindingRoot::State => (&self.state, "state"),
+            BindingRoot::Params => (&self.template_params, "params"),
+        }
+    }
+
+    fn push_scope(&mut self, data: Value, index: Option<usize>, untrusted: bool) {
+        self.scopes.push(DataScope {
+            data,
+            index,
+            untrusted,
+        });
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Current `$data` value: the innermost scope, or the payload at the card root.
+    fn current_data(&self) -> &Value {
+        self.scopes
+            .last()
+            .map(|scope| &scope.data)
+            .unwrap_or(&self.payload)
+    }
+
     pub fn lookup(&self, raw: &str) -> Option<Value> {
+        let (value, root) = self.resolve(raw)?;
+        let untrusted = match root {
+            "payload" | "session" => true,
+            // Outside any scope, `$data` is the payload.
+            "$data" => self.scopes.last().is_none_or(|scope| scope.untrusted),
+            _ => false,
+        };
+        let mut log = self.log.borrow_mut();
+        log.roots.push(root);
+        log.untrusted |= untrusted;
+        Some(value)
+    }
+
+    /// Resolves a binding path together with the root it was found in.
+    fn resolve(&self, raw: &str) -> Option<(Value, &'static str)> {
         let (path, default) = parse_binding_path(raw);
+        let path = normalize_path(&path);
         let mut segments = path.split('.');
         let first = segments.next()?;
-        let attempt_root = |root: &Value, rest: std::str::Split<'_, char>| lookup_in(root, rest);
 
         let found = match first {
-            "payload" => attempt_root(&self.payload, segments),
-            "session" => attempt_root(&self.session, segments),
-            "state" => attempt_root(&self.state, segments),
-            "params" | "template" => attempt_root(&self.template_params, segments),
-            _ => lookup_in(
-                &self.payload,
-                normalize_path(&path)
-                    .split('.')
-                    .collect::<Vec<_>>()
-                    .into_iter(),
-            )
-            .or_else(|| {
-                lookup_in(
-                    &self.session,
-                    normalize_path(&path)
-                        .split('.')
-                        .collect::<Vec<_>>()
-                        .into_iter(),
-                )
-            })
-            .or_else(|| {
-                lookup_in(
-                    &self.state,
-                    normalize_path(&path)
-                        .split('.')
-                        .collect::<Vec<_>>()
-                        .into_iter(),
-                )
-            })
-            .or_else(|| {
-                lookup_in(
-                    &self.template_params,
-                    normalize_path(&path)
-                        .split('.')
-                        .collect::<Vec<_>>()
-                        .into_iter(),
-                )
-            }),
+            "payload" => lookup_in(&self.payload, segments).map(|v| (v, "payload")),
+            "session" => lookup_in(&self.session, segments).map(|v| (v, "session")),
+            "state" => lookup_in(&self.state, segments).map(|v| (v, "state")),
+            "params" | "template" => {
+                lookup_in(&self.template_params, segments).map(|v| (v, "params"))
+            }
+            "$data" => lookup_in(self.current_data(), segments).map(|v| (v, "$data")),
+            "$root" => lookup_in(&self.payload, segments).map(|v| (v, "payload")),
+            "$index" => match (self.scopes.last().and_then(|s| s.index), segments.next()) {
+                (Some(index), None) => Some((Value::from(index), "$index")),
+                _ => None,
+            },
+            _ => self
+                .scopes
+                .last()
+                .and_then(|scope| lookup_in(&scope.data, path.split('.')))
+                .map(|v| (v, "$data"))
+                .or_else(|| self.resolve_bare(&path)),
         };
 
         match (found, default) {
-            (Some(value), _) if !value.is_null() => Some(value),
-            (None, Some(fallback)) | (Some(Value::Null), Some(fallback)) => Some(fallback),
+            (Some((value, root)), _) if !value.is_null() => Some((value, root)),
+            (None, Some(fallback)) | (Some((Value::Null, _)), Some(fallback)) => {
+                Some((fallback, "default"))
+            }
             (other, _) => other,
         }
     }
+
+    /// Resolves a bare path against the roots in precedence order, logging paths found in
+    /// more than one root. Under `strict_paths` bare paths are refused instead.
+    fn resolve_bare(&self, path: &str) -> Option<(Value, &'static str)> {
+        if self.strict_paths {
+            self.log.borrow_mut().unqualified.push(path.to_string());
+            return None;
+        }
+        let hits: Vec<(Value, &'static str)> = self
+            .precedence
+            .iter()
+            .filter_map(|root| {
+                let (value, name) = self.root(root);
+                lookup_in(value, path.split('.')).map(|v| (v, name))
+            })
+            .collect();
+        if hits.len() > 1 {
+            let roots = hits.iter().map(|(_, name)| *name).collect();
+            self.log
+                .borrow_mut()
+                .ambiguous
+                .push((path.to_string(), roots));
+        }
+        hits.into_iter().next()
+    }
 }
 
 fn lookup_in<'a, I>(value: &Value, mut parts: I) -> Option<Value>
@@ -360,116 +615,847 @@ where
     Some(current.clone())
 }
 
+/// Mutable state of one `${...}`/`@{...}` binding pass over a card.
+struct BindingPass<'a> {
+    engine: &'a dyn ExpressionEngine,
+    policy: BindingPolicy,
+    /// JSON pointer segments of the value currently being bound in the output card.
+    pointer: Vec<String>,
+    /// The same value's pointer segments in the card before binding.
+    source: Vec<String>,
+    /// Where each array item, and each dropped property, of the card before binding went.
+    moves: Vec<NodeMove>,
+    issues: Vec<ValidationIssue>,
+    summary: BindingSummary,
+    /// Per-binding provenance, collected when diagnostics are requested.
+    diagnostics: Option<Vec<BindingDiagnostic>>,
+    /// Bare paths refused by `strict_paths` in the most recent binding.
+    unqualified: Vec<String>,
+    escape_policy: EscapePolicy,
+    /// Whether the value being bound is rendered as markdown.
+    markdown: bool,
+    /// Whether the most recent binding read `payload` or `session` data, directly or
+    /// through `$data`.
+    reads_untrusted: bool,
+    /// Whether any binding since this was last reset did.
+    any_untrusted: bool,
+    /// Pointers of values bound from `payload` or `session` data.
+    untrusted: UntrustedPointers,
+}
+
+impl<'a> BindingPass<'a> {
+    fn new(
+        engine: &'a dyn ExpressionEngine,
+        policy: BindingPolicy,
+        summary: BindingSummary,
+    ) -> Self {
+        BindingPass {
+            engine,
+            policy,
+            pointer: Vec::new(),
+            source: Vec::new(),
+            // The card root stays in place; everything not moved by an array is under it.
+            moves: vec![NodeMove {
+                from: String::new(),
+                to: Some(String::new()),
+            }],
+            issues: Vec::new(),
+            summary,
+            diagnostics: None,
+            unqualified: Vec::new(),
+            escape_policy: EscapePolicy::None,
+            markdown: false,
+            reads_untrusted: false,
+            any_untrusted: false,
+            untrusted: UntrustedPointers::default(),
+        }
+    }
+
+    /// Records the outcome of one binding; `value` is `None` when it did not resolve.
+    fn record(&mut self, expression: &str, ctx: &BindingContext, value: Option<&Value>) {
+        let log = ctx.take_log();
+        let pointer = self.pointer();
+        let mut reported = BTreeSet::new();
+        for (path, roots) in log.ambiguous {
+            if reported.insert(path.clone()) {
+                self.issues.push(ValidationIssue {
+                    code: "ambiguous-binding-path".into(),
+                    message: format!(
+                        "bare path `{path}` exists in {}; resolved from {}",
+                        roots.join(", "),
+                        roots[0]
+                    ),
+                    path: pointer.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        for key in log.missing_translations {
+            if reported.insert(key.clone()) {
+                self.issues.push(ValidationIssue {
+                    code: "missing-translation".into(),
+                    message: format!(
+                        "no string table for {} defines `{key}`",
+                        ctx.translations.chain.join(", ")
+                    ),
+                    path: pointer.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+        self.unqualified = log.unqualified;
+        self.reads_untrusted = log.untrusted;
+        self.any_untrusted |= log.untrusted;
+        if self.reads_untrusted {
+            self.untrusted.insert(pointer.clone());
+        }
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            diagnostics.push(BindingDiagnostic {
+                pointer,
+                expression: expression.to_string(),
+                roots: log.roots.into_iter().map(str::to_string).collect(),
+                value_type: value.map(type_name).unwrap_or("missing").to_string(),
+            });
+        }
+    }
+
+    /// Escapes a resolved string for markdown text when it came from untrusted data,
+    /// unless the binding was marked `raw:`.
+    fn escape(&self, value: Value, raw: bool) -> Value {
+        match value {
+            Value::String(text)
+                if !raw
+                    && self.markdown
+                    && self.reads_untrusted
+                    && self.escape_policy != EscapePolicy::None =>
+            {
+                Value::String(escape_text(&text, &self.escape_policy))
+            }
+            other => other,
+        }
+    }
+
+    fn pointer(&self) -> String {
+        if self.pointer.is_empty() {
+            return "/".to_string();
+        }
+        join_pointer(&self.pointer)
+    }
+
+    /// Records where the item at `source` went: to the output items `kept` of the array
+    /// being bound, or nowhere.
+    fn moved(&mut self, kept: std::ops::Range<usize>) {
+        let from = join_pointer(&self.source);
+        let array = join_pointer(&self.pointer);
+        if kept.is_empty() {
+            self.moves.push(NodeMove { from, to: None });
+            return;
+        }
+        for index in kept {
+            self.moves.push(NodeMove {
+                from: from.clone(),
+                to: Some(format!("{array}/{index}")),
+            });
+        }
+    }
+
+    /// Applies the binding policy to an unresolved binding. `placeholder` is the original
+    /// template text, kept verbatim under `keep_placeholder`.
+    fn missing(&mut self, message: String, placeholder: &str) -> Result<Value, ComponentError> {
+        self.summary.missing_paths += 1;
+        let message = match self.unqualified.first() {
+            Some(path) => format!(
+                "unqualified binding path `{path}` is not allowed with strict_paths; \
+                 prefix it with payload., session., state. or params."
+            ),
+            None => message,
+        };
+        let replacement = match self.policy {
+            BindingPolicy::Error => return Err(ComponentError::Binding(message)),
+            BindingPolicy::Empty => Value::String(String::new()),
+            BindingPolicy::KeepPlaceholder => Value::String(placeholder.to_string()),
+            BindingPolicy::Null => Value::Null,
+        };
+        let path = self.pointer();
+        self.issues.push(ValidationIssue {
+            code: "missing-binding".into(),
+            message,
+            path,
+            ..Default::default()
+        });
+        Ok(replacement)
+    }
+}
+
 fn apply_bindings(
     value: &mut Value,
-    ctx: &BindingContext,
-    engine: &dyn ExpressionEngine,
-    summary: &mut BindingSummary,
+    ctx: &mut BindingContext,
+    pass: &mut BindingPass<'_>,
 ) -> Result<(), ComponentError> {
     match value {
         Value::String(text) => {
             if let Some(expr) = extract_expression(text) {
-                if is_simple_expression(expr) {
-                    if let Some(resolved) = ctx.lookup(expr) {
-                        *value = resolved;
-                        summary.placeholder_replacements += 1;
-                        return Ok(());
-                    }
-                    summary.missing_paths += 1;
-                    return Err(ComponentError::Binding(format!(
-                        "missing binding path: {expr}"
-                    )));
-                }
-                if let Some(resolved) = engine.eval(expr, ctx) {
-                    *value = match resolved {
-                        Value::String(_) => resolved,
-                        other => Value::String(stringify_value(&other)),
-                    };
-                    summary.expression_evaluations += 1;
-                    return Ok(());
-                }
-                summary.missing_paths += 1;
-                return Err(ComponentError::Binding(format!(
-                    "invalid expression: {expr}"
-                )));
+                let (expr, raw) = strip_raw(expr);
+                let resolved = eval_expression(expr, text, ctx, pass)?;
+                let resolved = pass.escape(resolved, raw);
+                *value = match resolved {
+                    Value::String(_) | Value::Null => resolved,
+                    other if !is_simple_expression(expr) => Value::String(stringify_value(&other)),
+                    other => other,
+                };
+                return Ok(());
             }
             if let Some(path) = extract_single_placeholder(text) {
-                if let Some(resolved) = ctx.lookup(path) {
-                    *value = resolved;
-                    summary.placeholder_replacements += 1;
+                let (path, raw) = strip_raw(path);
+                let resolved = ctx.lookup(path);
+                pass.record(path, ctx, resolved.as_ref());
+                if let Some(resolved) = resolved {
+                    *value = pass.escape(resolved, raw);
+                    pass.summary.placeholder_replacements += 1;
                     return Ok(());
                 }
-                summary.missing_paths += 1;
-                return Err(ComponentError::Binding(format!(
-                    "missing binding path: {path}"
-                )));
+                *value = pass.missing(format!("missing binding path: {path}"), text)?;
+                return Ok(());
             }
-            let replaced = replace_placeholders(text, ctx, summary)?;
+            let replaced = replace_placeholders(text, ctx, pass)?;
             *value = Value::String(replaced);
             Ok(())
         }
         Value::Array(items) => {
-            for item in items {
-                apply_bindings(item, ctx, engine, summary)?;
+            let mut expanded = Vec::with_capacity(items.len());
+            for (index, mut item) in std::mem::take(items).into_iter().enumerate() {
+                let first = expanded.len();
+                pass.source.push(index.to_string());
+                pass.pointer.push(expanded.len().to_string());
+                let data = match item.as_object_mut() {
+                    Some(map) => take_data_binding(map, ctx, pass),
+                    None => Ok(None),
+                };
+                pass.pointer.pop();
+                match data? {
+                    // `$data` bound to an array repeats the element once per entry.
+                    Some((Value::Array(entries), untrusted)) => {
+                        for (index, entry) in entries.into_iter().enumerate() {
+                            ctx.push_scope(entry, Some(index), untrusted);
+                            pass.pointer.push(expanded.len().to_string());
+                            let result = bind_element(item.clone(), ctx, pass);
+                            pass.pointer.pop();
+                            ctx.pop_scope();
+                            if let Some(bound) = result? {
+                                pass.summary.repeated_elements += 1;
+                                expanded.push(bound);
+                            }
+                        }
+                    }
+                    Some((data, untrusted)) => {
+                        ctx.push_scope(data, None, untrusted);
+                        pass.pointer.push(expanded.len().to_string());
+                        let result = bind_element(item, ctx, pass);
+                        pass.pointer.pop();
+                        ctx.pop_scope();
+                        expanded.extend(result?);
+                    }
+                    None => {
+                        pass.pointer.push(expanded.len().to_string());
+                        let result = bind_element(item, ctx, pass);
+                        pass.pointer.pop();
+                        expanded.extend(result?);
+                    }
+                }
+                pass.moved(first..expanded.len());
+                pass.source.pop();
             }
+            *items = expanded;
             Ok(())
         }
         Value::Object(map) => {
-            for entry in map.values_mut() {
-                apply_bindings(entry, ctx, engine, summary)?;
+            // Outside of an array there is nothing to repeat, so `$data` only rescopes.
+            let scoped = match take_data_binding(map, ctx, pass)? {
+                Some((data, untrusted)) => {
+                    ctx.push_scope(data, None, untrusted);
+                    true
+                }
+                None => false,
+            };
+            // Only the card root reaches here with `$when` still set. There is no parent
+            // to drop it from, so a falsy condition leaves no card to render.
+            let result = match map.remove("$when") {
+                Some(condition) => match eval_condition(&condition, ctx, pass) {
+                    Ok(true) => bind_entries(map, ctx, pass),
+                    Ok(false) => Err(ComponentError::Binding(
+                        "card-level $when is false; there is no card to render".into(),
+                    )),
+                    Err(err) => Err(err),
+                },
+                None => bind_entries(map, ctx, pass),
+            };
+            if scoped {
+                ctx.pop_scope();
             }
-            Ok(())
+            result
         }
         _ => Ok(()),
     }
 }
 
+fn bind_entries(
+    map: &mut Map<String, Value>,
+    ctx: &mut BindingContext,
+    pass: &mut BindingPass<'_>,
+) -> Result<(), ComponentError> {
+    let mut dropped = Vec::new();
+    let text_block = map.get("type").and_then(Value::as_str) == Some("TextBlock");
+    let fact = pass.pointer.len() >= 2 && pass.pointer[pass.pointer.len() - 2] == "facts";
+    let enclosing_markdown = pass.markdown;
+    for (key, entry) in map.iter_mut() {
+        pass.pointer.push(key.clone());
+        pass.source.push(key.clone());
+        pass.markdown =
+            (text_block && key == "text") || (fact && matches!(key.as_str(), "title" | "value"));
+        let result = bind_element(std::mem::take(entry), ctx, pass);
+        pass.markdown = enclosing_markdown;
+        pass.pointer.pop();
+        if matches!(result, Ok(None)) {
+            pass.moves.push(NodeMove {
+                from: join_pointer(&pass.source),
+                to: None,
+            });
+        }
+        pass.source.pop();
+        match result? {
+            Some(bound) => *entry = bound,
+            None => dropped.push(key.clone()),
+        }
+    }
+    for key in dropped {
+        map.remove(&key);
+    }
+    Ok(())
+}
+
+/// Binds a single element in the current scope, returning `None` when its `$when`
+/// condition is falsy and the element must be dropped.
+fn bind_element(
+    mut element: Value,
+    ctx: &mut BindingContext,
+    pass: &mut BindingPass<'_>,
+) -> Result<Option<Value>, ComponentError> {
+    if let Some(map) = element.as_object_mut()
+        && let Some(condition) = map.remove("$when")
+        && !eval_condition(&condition, ctx, pass)?
+    {
+        pass.summary.removed_elements += 1;
+        if let Some(kind) = map.get("type").and_then(|v| v.as_str()) {
+            pass.summary.removed_element_types.insert(kind.to_string());
+        }
+        return Ok(None);
+    }
+    apply_bindings(&mut element, ctx, pass)?;
+    Ok(Some(element))
+}
+
+/// Evaluates a `$when` condition; conditions that cannot be resolved count as false.
+fn eval_condition(
+    condition: &Value,
+    ctx: &BindingContext,
+    pass: &mut BindingPass<'_>,
+) -> Result<bool, ComponentError> {
+    let Some(text) = condition.as_str() else {
+        return Ok(truthy(condition));
+    };
+    let expr = extract_expression(text).unwrap_or(text.trim());
+    pass.summary.expression_evaluations += 1;
+    let value = pass.engine.try_eval(expr, ctx)?;
+    pass.record(expr, ctx, value.as_ref());
+    Ok(value.map(|value| truthy(&value)).unwrap_or(false))
+}
+
+/// Evaluates a `${...}` expression body, returning the typed result. `placeholder` is the
+/// template text the expression came from, used when the binding policy keeps it.
+fn eval_expression(
+    expr: &str,
+    placeholder: &str,
+    ctx: &BindingContext,
+    pass: &mut BindingPass<'_>,
+) -> Result<Value, ComponentError> {
+    if is_simple_expression(expr) {
+        let resolved = ctx.lookup(expr);
+        pass.record(expr, ctx, resolved.as_ref());
+        if let Some(resolved) = resolved {
+            pass.summary.placeholder_replacements += 1;
+            return Ok(resolved);
+        }
+        return pass.missing(format!("missing binding path: {expr}"), placeholder);
+    }
+    let resolved = pass.engine.try_eval(expr, ctx)?;
+    pass.record(expr, ctx, resolved.as_ref());
+    if let Some(resolved) = resolved {
+        pass.summary.expression_evaluations += 1;
+        return Ok(resolved);
+    }
+    pass.missing(format!("invalid expression: {expr}"), placeholder)
+}
+
+/// Removes the templating `$data` property from an element and resolves it in the
+/// enclosing scope, together with whether any of it was bound from `payload` or `session`.
+fn take_data_binding(
+    map: &mut Map<String, Value>,
+    ctx: &mut BindingContext,
+    pass: &mut BindingPass<'_>,
+) -> Result<Option<(Value, bool)>, ComponentError> {
+    let Some(mut data) = map.remove("$data") else {
+        return Ok(None);
+    };
+    pass.pointer.push("$data".to_string());
+    pass.source.push("$data".to_string());
+    let enclosing = std::mem::take(&mut pass.any_untrusted);
+    let result = if let Some(text) = data.as_str()
+        && let Some(expr) = extract_expression(text)
+    {
+        eval_expression(expr, text, ctx, pass)
+    } else {
+        apply_bindings(&mut data, ctx, pass).map(|_| data)
+    };
+    let untrusted = pass.any_untrusted;
+    pass.any_untrusted |= enclosing;
+    pass.pointer.pop();
+    pass.source.pop();
+    result.map(|data| Some((data, untrusted)))
+}
+
 fn apply_handlebars(
     value: &mut Value,
     inv: &AdaptiveCardInvocation,
+    translations: &Translations,
+    hash: Option<&str>,
     summary: &mut BindingSummary,
-) -> Result<(), ComponentError> {
+) -> Result<UntrustedPointers, ComponentError> {
+    let mut untrusted = UntrustedPointers::default();
+    let mut refs = PartialRefs::default();
+    if !scan_templates(value, &mut refs) {
+        return Ok(untrusted);
+    }
+    let partials = load_partials(refs, &inv.card_spec)?;
+    let key = hash.map(|hash| template_cache_key(hash, &partials));
+    let (engine, stats) =
+        template_cache::compiled_card(key.as_deref(), || compile_templates(value, &partials))?;
+    summary.template_cache = stats;
+    let context = build_handlebars_context(inv, translations, &Locale::from_invocation(inv));
+    let mut trusted = context.clone();
+    trusted["payload"] = Value::Null;
+    trusted["session"] = Value::Null;
+    let contexts = HandlebarsContexts {
+        full: &context,
+        trusted: &trusted,
+    };
+    let mut render = HandlebarsRender {
+        engine: &engine,
+        contexts: &contexts,
+        typed: inv.handlebars_mode == HandlebarsMode::Typed,
+        summary,
+        untrusted: &mut untrusted,
+    };
+    render.value(value, String::new(), false)?;
+    Ok(untrusted)
+}
+
+/// The Handlebars context, and the same context without `payload` and `session` for
+/// telling which rendered URLs depend on untrusted data.
+struct HandlebarsContexts<'a> {
+    full: &'a Value,
+    trusted: &'a Value,
+}
+
+/// Partials referenced by a card's templates, and the inline partials they define.
+#[derive(Default)]
+struct PartialRefs {
+    names: Vec<String>,
+    inline: BTreeSet<String>,
+}
+
+/// Whether any string of the card is a template, collecting the partial references of
+/// those that are in the same walk.
+fn scan_templates(value: &Value, refs: &mut PartialRefs) -> bool {
+    match value {
+        Value::String(text) if is_template(text) => {
+            scan_partial_refs(text, &mut refs.names, &mut refs.inline);
+            true
+        }
+        Value::Array(items) => items
+            .iter()
+            .fold(false, |found, item| scan_templates(item, refs) || found),
+        Value::Object(map) => map
+            .values()
+            .fold(false, |found, entry| scan_templates(entry, refs) || found),
+        _ => false,
+    }
+}
+
+/// Cache key of a card's compiled templates: its content hash, combined with the sources of
+/// the partials it loads so that an edited partial is compiled afresh.
+fn template_cache_key(hash: &str, partials: &BTreeMap<String, String>) -> String {
+    if partials.is_empty() {
+        return hash.to_string();
+    }
+    let mut bytes = hash.as_bytes().to_vec();
+    for (name, source) in partials {
+        bytes.push(0);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(source.as_bytes());
+    }
+    hash_bytes(&bytes)
+}
+
+/// Builds a registry with the helpers, the card's partials, and every templated string of
+/// the card registered under its own source text.
+fn compile_templates(
+    card: &Value,
+    partials: &BTreeMap<String, String>,
+) -> Result<CompiledCard, ComponentError> {
     let mut engine = Handlebars::new();
     engine.set_strict_mode(false);
-    let context = build_handlebars_context(inv);
-    render_handlebars_value(value, &engine, &context, summary)
+    handlebars_helpers::register_helpers(&mut engine);
+    for (name, source) in partials {
+        engine
+            .register_partial(name, source)
+            .map_err(|err| ComponentError::Binding(format!("handlebars partial {name}: {err}")))?;
+    }
+    let mut templates = BTreeSet::new();
+    collect_templates(card, &mut templates);
+    // The typed rewrites are registered too, so one cached registry serves both modes.
+    let typed: Vec<String> = templates.iter().filter_map(|t| typed_template(t)).collect();
+    for template in templates
+        .iter()
+        .copied()
+        .chain(typed.iter().map(String::as_str))
+    {
+        engine
+            .register_template_string(template, protect_card_macros(template))
+            .map_err(|err| ComponentError::Binding(format!("handlebars: {err}")))?;
+    }
+    Ok(engine)
 }
 
-fn render_handlebars_value(
-    value: &mut Value,
-    engine: &Handlebars<'_>,
-    context: &Value,
-    summary: &mut BindingSummary,
-) -> Result<(), ComponentError> {
+/// The template rendering a string that consists solely of one expression as JSON text:
+/// `{{payload.count}}` becomes `{{{json payload.count}}}`, a helper call such as
+/// `{{default a b}}` becomes `{{{json (default a b)}}}`, and `{{{json x}}}` is kept.
+/// Blocks, partials, comments and strings with surrounding text yield `None`.
+fn typed_template(text: &str) -> Option<String> {
+    if locale::has_card_macros(text) {
+        return None;
+    }
+    let inner = text.strip_prefix("{{")?.strip_suffix("}}")?;
+    let inner = match inner.strip_prefix('{') {
+        Some(rest) => rest.strip_suffix('}')?,
+        None => inner,
+    };
+    let expr = inner.trim().trim_matches('~').trim();
+    let expr = expr.strip_prefix('&').unwrap_or(expr).trim_start();
+    if expr.is_empty()
+        || expr == "else"
+        || expr.contains("{{")
+        || expr.contains("}}")
+        || expr.starts_with(['#', '/', '>', '!', '^', '{', '*'])
+    {
+        return None;
+    }
+    let param = match expr.strip_prefix("json") {
+        Some(rest) if rest.starts_with(char::is_whitespace) => rest.trim().to_string(),
+        _ if expr.contains(char::is_whitespace) => format!("({expr})"),
+        _ => expr.to_string(),
+    };
+    Some(format!("{{{{{{json {param}}}}}}}"))
+}
+
+/// Whether a string needs Handlebars: it contains `{{` other than the Adaptive Card
+/// `{{DATE(...)}}`/`{{TIME(...)}}` text functions, which are expanded after binding.
+fn is_template(text: &str) -> bool {
+    text.contains("{{")
+        && (!locale::has_card_macros(text)
+            || text
+                .replace("{{DATE(", "")
+                .replace("{{TIME(", "")
+                .contains("{{"))
+}
+
+/// Escapes the Adaptive Card text functions so Handlebars copies them verbatim.
+fn protect_card_macros(text: &str) -> String {
+    text.replace("{{DATE(", "\\{{DATE(")
+        .replace("{{TIME(", "\\{{TIME(")
+}
+
+fn expand_card_macros(value: &mut Value, locale: &Locale) {
     match value {
         Value::String(text) => {
-            let rendered = engine
-                .render_template(text, context)
-                .map_err(|err| ComponentError::Binding(format!("handlebars: {err}")))?;
-            *value = Value::String(rendered);
-            summary.handlebars_expansions += 1;
-            Ok(())
+            if let Some(expanded) = locale::expand_card_macros(text, locale) {
+                *text = expanded;
+            }
         }
-        Value::Array(items) => {
-            for item in items {
-                render_handlebars_value(item, engine, context, summary)?;
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| expand_card_macros(item, locale)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|entry| expand_card_macros(entry, locale)),
+        _ => {}
+    }
+}
+
+fn collect_templates<'v>(value: &'v Value, templates: &mut BTreeSet<&'v str>) {
+    match value {
+        Value::String(text) if is_template(text) => {
+            templates.insert(text);
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_templates(item, templates)),
+        Value::Object(map) => map
+            .values()
+            .for_each(|entry| collect_templates(entry, templates)),
+        _ => {}
+    }
+}
+
+/// Loads the sources of every partial the card references, directly or through other
+/// partials, by name: from the asset registry/catalog mapping, `<base>/partials/<name>.hbs`,
+/// or the host asset resolver. A card without partial references reads nothing.
+fn load_partials(
+    refs: PartialRefs,
+    spec: &CardSpec,
+) -> Result<BTreeMap<String, String>, ComponentError> {
+    let PartialRefs {
+        names: mut pending,
+        mut inline,
+    } = refs;
+    let mut partials = BTreeMap::new();
+    while let Some(name) = pending.pop() {
+        if inline.contains(&name) || partials.contains_key(&name) {
+            continue;
+        }
+        let source = load_partial(&name, spec)?;
+        scan_partial_refs(&source, &mut pending, &mut inline);
+        partials.insert(name, source);
+    }
+    Ok(partials)
+}
+
+/// Finds `{{> name}}`/`{{#> name}}` references and `{{#*inline "name"}}` definitions.
+/// Dynamic partial names (`{{> (lookup ...)}}`) cannot be resolved ahead of time and are
+/// skipped.
+fn scan_partial_refs(text: &str, names: &mut Vec<String>, inline: &mut BTreeSet<String>) {
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let tag = rest[start + 2..]
+            .trim_start_matches(['{', '~'])
+            .trim_start();
+        let (is_inline, body) = match tag.strip_prefix("#*inline") {
+            Some(body) => (true, Some(body)),
+            None => (
+                false,
+                tag.strip_prefix('>').or_else(|| tag.strip_prefix("#>")),
+            ),
+        };
+        if let Some(body) = body {
+            let name: String = body
+                .trim_start()
+                .trim_start_matches(['"', '\''])
+                .chars()
+                .take_while(|c| !c.is_whitespace() && !matches!(c, '}' | '~' | '"' | '\'' | '('))
+                .collect();
+            if !name.is_empty() {
+                if is_inline {
+                    inline.insert(name);
+                } else {
+                    names.push(name);
+                }
             }
-            Ok(())
         }
-        Value::Object(map) => {
-            for entry in map.values_mut() {
-                render_handlebars_value(entry, engine, context, summary)?;
+        rest = &rest[start + 2..];
+    }
+}
+
+/// Whether any string of the card calls `t(...)` or the `{{t ...}}` helper.
+fn uses_translations(value: &Value) -> bool {
+    match value {
+        Value::String(text) => {
+            text.contains("{{t ")
+                || text.contains("(t ")
+                || text.match_indices("t(").any(|(at, _)| {
+                    !text[..at]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+                })
+        }
+        Value::Array(items) => items.iter().any(uses_translations),
+        Value::Object(map) => map.values().any(uses_translations),
+        _ => false,
+    }
+}
+
+/// Loads and merges the `<name>.<locale>.json` string tables along the locale fallback
+/// chain. Locales without a table are skipped.
+fn load_translations(inv: &AdaptiveCardInvocation) -> Result<Translations, ComponentError> {
+    let spec = &inv.card_spec;
+    let requested = i18n::requested_locale(inv);
+    let default = spec
+        .default_locale
+        .as_deref()
+        .unwrap_or(i18n::DEFAULT_LOCALE);
+    let chain = i18n::locale_chain(requested.as_deref(), default);
+    let mut translations = Translations {
+        locale: chain.first().cloned().unwrap_or_default(),
+        chain: chain.clone(),
+        ..Default::default()
+    };
+    let (name, dir) = match (&spec.strings, &inv.card_source) {
+        (Some(name), _) => (name.clone(), Some(asset_base_path())),
+        (None, CardSource::Catalog) => match &spec.catalog_name {
+            Some(name) => (
+                name.trim_start_matches('/').to_string(),
+                Some(asset_base_path()),
+            ),
+            None => return Ok(translations),
+        },
+        (None, CardSource::Asset) => match &spec.asset_path {
+            Some(path) => (path.trim_end_matches(".json").to_string(), None),
+            None => return Ok(translations),
+        },
+        (None, CardSource::Inline) => return Ok(translations),
+    };
+    for locale in &chain {
+        let key = format!("{name}.{locale}");
+        let mut candidates = Vec::new();
+        if let Some(mapped) = resolve_catalog_mapping(&key, spec)? {
+            candidates.push(mapped);
+        }
+        candidates.push(match &dir {
+            Some(dir) => format!("{dir}/{key}.json"),
+            None => format!("{key}.json"),
+        });
+        if let Some(host) = resolve_with_host(&key).map_err(|e| ComponentError::Asset(e.message))? {
+            candidates.push(host);
+        }
+        for candidate in candidates {
+            match load_card_from_path(&candidate) {
+                Ok((table, _)) => {
+                    translations.merge(&table);
+                    break;
+                }
+                Err(ComponentError::AssetNotFound(_)) => continue,
+                Err(err) => return Err(err),
             }
-            Ok(())
         }
-        _ => Ok(()),
     }
+    Ok(translations)
+}
+
+fn load_partial(name: &str, spec: &CardSpec) -> Result<String, ComponentError> {
+    let mut candidates = Vec::new();
+    if let Some(mapped) = resolve_catalog_mapping(name, spec)? {
+        candidates.push(mapped);
+    }
+    candidates.push(format!("{}/partials/{name}.hbs", asset_base_path()));
+    if let Some(host) = resolve_with_host(name).map_err(|e| ComponentError::Asset(e.message))? {
+        candidates.push(host);
+    }
+    for candidate in candidates {
+        match std::fs::read_to_string(&candidate) {
+            Ok(source) => return Ok(source.trim_end_matches(['\r', '\n']).to_string()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(ComponentError::Io(err)),
+        }
+    }
+    Err(ComponentError::AssetNotFound(format!("partial {name}")))
+}
+
+struct HandlebarsRender<'a> {
+    engine: &'a CompiledCard,
+    contexts: &'a HandlebarsContexts<'a>,
+    typed: bool,
+    summary: &'a mut BindingSummary,
+    /// Pointers of URL properties whose rendering depends on untrusted data.
+    untrusted: &'a mut UntrustedPointers,
+}
+
+impl HandlebarsRender<'_> {
+    /// Renders every templated string in place. With `trace`, a string whose rendering
+    /// changes without `payload` and `session` is recorded as untrusted at `path`; only
+    /// URL properties are traced.
+    fn value(
+        &mut self,
+        value: &mut Value,
+        path: String,
+        trace: bool,
+    ) -> Result<(), ComponentError> {
+        match value {
+            Value::String(text) if is_template(text) => {
+                let typed_source = self.typed.then(|| typed_template(text)).flatten();
+                let source = typed_source.as_deref().unwrap_or(text);
+                let rendered = self
+                    .engine
+                    .render(source, self.contexts.full)
+                    .map_err(|err| ComponentError::Binding(format!("handlebars: {err}")))?;
+                let untrusted = trace
+                    && self
+                        .engine
+                        .render(source, self.contexts.trusted)
+                        .map_or(true, |trusted| trusted != rendered);
+                if untrusted {
+                    self.untrusted.insert(path);
+                }
+                *value = match typed_source {
+                    Some(_) => serde_json::from_str(&rendered).unwrap_or(Value::String(rendered)),
+                    None => Value::String(rendered),
+                };
+                self.summary.handlebars_expansions += 1;
+                Ok(())
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    self.value(item, format!("{path}/{index}"), false)?;
+                }
+                Ok(())
+            }
+            Value::Object(map) => {
+                for (key, entry) in map.iter_mut() {
+                    let trace = url_safety::URL_KEYS.contains(&key.as_str());
+                    let entry_path = format!("{path}/{}", compatibility::escape_pointer(key));
+                    self.value(entry, entry_path, trace)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// JSON pointer of `segments`; the card root is the empty pointer.
+fn join_pointer(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|segment| format!("/{}", compatibility::escape_pointer(segment)))
+        .collect()
 }
 
-fn build_handlebars_context(inv: &AdaptiveCardInvocation) -> Value {
+fn build_handlebars_context(
+    inv: &AdaptiveCardInvocation,
+    translations: &Translations,
+    locale: &Locale,
+) -> Value {
     let mut root = Map::new();
     root.insert("payload".to_owned(), inv.payload.clone());
+    root.insert("session".to_owned(), inv.session.clone());
     root.insert("state".to_owned(), inv.state.clone());
+    root.insert(
+        "i18n".to_owned(),
+        serde_json::json!({
+            "locale": locale.tag,
+            "time_zone": locale.time_zone,
+            "strings": translations.strings,
+        }),
+    );
 
     if let Some(node_id) = inv.node_id.as_deref() {
         root.insert("node_id".to_owned(), Value::String(node_id.to_owned()));
@@ -506,18 +1492,17 @@ fn resolve_state_input(state: &Value) -> Option<Map<String, Value>> {
 fn is_reserved_handlebars_key(key: &str) -> bool {
     matches!(
         key,
-        "payload" | "state" | "node" | "node_id" | "node_payload"
+        "payload" | "session" | "state" | "node" | "node_id" | "node_payload" | "i18n"
     )
 }
 
 fn replace_placeholders(
     input: &str,
     ctx: &BindingContext,
-    summary: &mut BindingSummary,
+    pass: &mut BindingPass<'_>,
 ) -> Result<String, ComponentError> {
     let mut output = String::new();
     let mut cursor = 0;
-    let bytes = input.as_bytes();
     while cursor < input.len() {
         let remaining = &input[cursor..];
         let next_at = remaining.find("@{");
@@ -538,25 +1523,29 @@ fn replace_placeholders(
         output.push_str(&input[cursor..absolute]);
 
         let marker = input.as_bytes()[absolute];
-        if absolute + 2 > input.len() || bytes[absolute + 1] != b'{' {
-            output.push_str(&input[absolute..]);
-            break;
-        }
         let rest = &input[absolute + 2..];
-        if let Some(end) = rest.find('}') {
-            let path = &rest[..end];
-            let Some(replacement) = ctx.lookup(path.trim()) else {
-                summary.missing_paths += 1;
-                return Err(ComponentError::Binding(format!(
-                    "missing binding path: {path}"
-                )));
-            };
-            let replacement = match replacement {
-                Value::String(s) => s,
-                other => other.to_string(),
+        if let Some(end) = closing_brace(rest) {
+            let (body, raw) = strip_raw(rest[..end].trim());
+            let placeholder = &input[absolute..absolute + 2 + end + 1];
+            let missing_before = pass.summary.missing_paths;
+            let resolved = if marker == b'$' {
+                eval_expression(body, placeholder, ctx, pass)?
+            } else {
+                let resolved = ctx.lookup(body);
+                pass.record(body, ctx, resolved.as_ref());
+                match resolved {
+                    Some(resolved) => {
+                        pass.summary.placeholder_replacements += 1;
+                        resolved
+                    }
+                    None => pass.missing(format!("missing binding path: {body}"), placeholder)?,
+                }
             };
-            output.push_str(&replacement);
-            summary.placeholder_replacements += 1;
+            // A `null` policy substitute has no text form inside a larger string.
+            if !(resolved.is_null() && pass.summary.missing_paths > missing_before) {
+                let text = Value::String(stringify_value(&resolved));
+                output.push_str(&stringify_value(&pass.escape(text, raw)));
+            }
             cursor = absolute + 2 + end + 1;
         } else {
             output.push(marker as char);
@@ -567,6 +1556,41 @@ fn replace_placeholders(
     Ok(output)
 }
 
+/// Byte offset of the `}` closing a placeholder whose body starts at `body`, skipping
+/// nested braces and quoted strings so expressions like `${a ? "}" : b}` stay intact.
+fn closing_brace(body: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (idx, ch) in body.char_indices() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        match ch {
+            '"' | '\'' => quote = Some(ch),
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(idx),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the placeholder body when the whole (trimmed) string is a single placeholder.
+fn whole_placeholder<'a>(input: &'a str, marker: &str) -> Option<&'a str> {
+    let body = input.trim().strip_prefix(marker)?;
+    let end = closing_brace(body)?;
+    (end == body.len() - 1).then(|| body[..end].trim())
+}
+
 fn hash_bytes(bytes: &[u8]) -> String {
     format!("blake3:{}", blake3::hash(bytes).to_hex())
 }
@@ -577,11 +1601,7 @@ fn hash_json(value: &Value) -> Option<String> {
 }
 
 fn extract_single_placeholder(input: &str) -> Option<&str> {
-    let trimmed = input.trim();
-    if let Some(stripped) = trimmed.strip_prefix("@{").and_then(|s| s.strip_suffix('}')) {
-        return Some(stripped.trim());
-    }
-    None
+    whole_placeholder(input, "@{")
 }
 
 fn parse_binding_path(raw: &str) -> (String, Option<Value>) {
@@ -599,20 +1619,75 @@ fn parse_binding_path(raw: &str) -> (String, Option<Value>) {
     (path, default)
 }
 
-fn extract_expression(input: &str) -> Option<&str> {
-    let trimmed = input.trim();
-    if let Some(stripped) = trimmed.strip_prefix("${").and_then(|s| s.strip_suffix('}')) {
-        return Some(stripped.trim());
+/// Splits the `raw:` escaping opt-out prefix off a binding body.
+fn strip_raw(body: &str) -> (&str, bool) {
+    match body.strip_prefix("raw:") {
+        Some(rest) => (rest.trim_start(), true),
+        None => (body, false),
     }
-    None
 }
 
-fn is_simple_expression(expr: &str) -> bool {
-    let trimmed = expr.trim();
-    if trimmed.chars().any(|c| c.is_whitespace()) {
-        return false;
+/// Escapes untrusted text for Adaptive Card markdown. Emphasis, code, strike-through and
+/// link punctuation is backslash-escaped anywhere; list markers only at the start of a line.
+fn escape_text(text: &str, policy: &EscapePolicy) -> String {
+    let strict = *policy == EscapePolicy::Strict;
+    let mut out = String::with_capacity(text.len());
+    let mut line_start = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' if strict => out.push_str("&amp;"),
+            '<' if strict => out.push_str("&lt;"),
+            '>' if strict => out.push_str("&gt;"),
+            c if strict && c.is_control() => out.push(' '),
+            '\\' | '*' | '_' | '`' | '~' | '[' | ']' | '(' | ')' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '-' | '+' if line_start && chars.peek().is_some_and(|n| n.is_whitespace()) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if line_start && c.is_ascii_digit() => {
+                out.push(c);
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    out.push(digit);
+                }
+                if chars.next_if_eq(&'.').is_some() {
+                    out.push_str("\\.");
+                }
+            }
+            c => out.push(c),
+        }
+        line_start = c == '\n' || (line_start && c == ' ');
     }
-    !trimmed.contains('?') && !trimmed.contains("==") && !trimmed.contains(':')
+    out
+}
+
+fn extract_expression(input: &str) -> Option<&str> {
+    whole_placeholder(input, "${")
+}
+
+/// Plain paths are looked up directly and keep their JSON type; anything else goes
+/// through the expression engine. A path followed by `|| default` keeps the meaning it had
+/// before expressions were parsed: the default is a literal (a JSON value or a bare word,
+/// as in `${payload.name || Guest}`) used when the path is missing or null, not the
+/// right-hand side of a logical or.
+fn is_simple_expression(expr: &str) -> bool {
+    let (path, default) = match expr.split_once("||") {
+        Some((path, default)) => (path.trim(), Some(default.trim())),
+        None => (expr.trim(), None),
+    };
+    !path.is_empty()
+        && path
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '[' | ']' | '$' | '@'))
+        && default.is_none_or(|default| {
+            serde_json::from_str::<Value>(default).is_ok()
+                || default
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        })
 }
 
 fn normalize_path(path: &str) -> String {
@@ -629,6 +1704,9 @@ pub fn analyze_features(card: &Value) -> CardFeatureSummary {
             .get("version")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
+        minimum_version: card
+            .is_object()
+            .then(|| compatibility::format_version(compatibility::minimum_version(card))),
         ..CardFeatureSummary::default()
     };
 
@@ -702,6 +1780,7 @@ pub fn validate_card(card: &Value) -> Vec<ValidationIssue> {
             code: "invalid-root".into(),
             message: "Card must be a JSON object".into(),
             path: "/".into(),
+            ..Default::default()
         });
         return issues;
     }
@@ -712,6 +1791,7 @@ pub fn validate_card(card: &Value) -> Vec<ValidationIssue> {
             code: "invalid-type".into(),
             message: "Root type must be AdaptiveCard".into(),
             path: "/type".into(),
+            ..Default::default()
         });
     }
     if card.get("version").is_none() {
@@ -719,6 +1799,7 @@ pub fn validate_card(card: &Value) -> Vec<ValidationIssue> {
             code: "missing-version".into(),
             message: "AdaptiveCard must include a version".into(),
             path: "/version".into(),
+            ..Default::default()
         });
     }
 
@@ -729,6 +1810,7 @@ pub fn validate_card(card: &Value) -> Vec<ValidationIssue> {
             code: code.to_string(),
             message: message.to_string(),
             path: path.to_string(),
+            ..Default::default()
         });
     }
 
@@ -758,6 +1840,15 @@ pub fn validate_card(card: &Value) -> Vec<ValidationIssue> {
                         );
                     }
                 }
+                // Interactions name the Submit or Execute action that posted back by its id.
+                if matches!(kind, "Action.Submit" | "Action.Execute") && !map.contains_key("id") {
+                    push_issue(
+                        path,
+                        "missing-action-id",
+                        "Submit and Execute actions should include an id",
+                        issues,
+                    );
+                }
                 if kind.starts_with("Action.") {
                     if let Some(id) = map.get("id").and_then(|v| v.as_str())
                         && !action_ids.insert(id.to_string())
@@ -1035,5 +2126,21 @@ pub fn validate_card(card: &Value) -> Vec<ValidationIssue> {
 
     let mut action_ids = HashSet::new();
     visit(card, "", &mut issues, &mut input_ids, &mut action_ids);
+    // A type newer than the declared version is reported once, as `requires-newer-version`,
+    // rather than also as a schema enum failure on its `type`.
+    let versions = compatibility::validate_versions(card);
+    let newer_types: HashSet<String> = versions
+        .iter()
+        .map(|issue| format!("{}/type", issue.path))
+        .collect();
+    issues.extend(
+        validation::validate_card_schema(card)
+            .into_iter()
+            .filter(|issue| {
+                issue.code != "schema-unknown-type" || !newer_types.contains(&issue.path)
+            }),
+    );
+    issues.extend(versions);
+    issues.extend(references::validate_references(card));
     issues
 }
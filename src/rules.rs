@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+use crate::model::Severity::{self, Error, Info, Warning};
+use crate::model::{RuleLevel, ValidationIssue};
+
+/// A card validation rule: its stable issue code and default severity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationRule {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+const fn rule(code: &'static str, severity: Severity, description: &'static str) -> ValidationRule {
+    ValidationRule {
+        code,
+        severity,
+        description,
+    }
+}
+
+const CATALOGUE: &[ValidationRule] = &[
+    // Card structure.
+    rule("invalid-root", Error, "Card is not a JSON object"),
+    rule("invalid-type", Error, "Root type is not AdaptiveCard"),
+    rule("missing-version", Error, "Card has no version"),
+    rule("invalid-body", Error, "body is not an array"),
+    rule("invalid-actions", Error, "actions is not an array"),
+    rule("missing-id", Error, "Input without an id"),
+    rule("duplicate-id", Error, "Two inputs share an id"),
+    rule("duplicate-action-id", Warning, "Two actions share an id"),
+    rule(
+        "missing-action-id",
+        Warning,
+        "Action.Submit or Action.Execute without an id",
+    ),
+    rule("missing-choices", Error, "Input.ChoiceSet without choices"),
+    rule(
+        "invalid-choices",
+        Error,
+        "Input.ChoiceSet choices is not an array",
+    ),
+    rule("empty-choices", Error, "Input.ChoiceSet with no choices"),
+    rule("invalid-choice", Error, "Choice without a title or value"),
+    rule("missing-title", Warning, "Input.Toggle without a title"),
+    rule("invalid-range", Error, "Input.Number min greater than max"),
+    rule(
+        "invalid-columns",
+        Error,
+        "ColumnSet columns is not an array",
+    ),
+    rule("empty-columns", Error, "ColumnSet with no columns"),
+    rule("missing-sources", Error, "Media without sources"),
+    rule("invalid-sources", Error, "Media sources is not an array"),
+    rule("invalid-source", Error, "Media source without a url"),
+    rule("missing-url", Error, "Action.OpenUrl without a url"),
+    rule("missing-verb", Warning, "Action.Execute without a verb"),
+    rule(
+        "invalid-data",
+        Warning,
+        "Action.Execute data is not an object",
+    ),
+    rule("missing-card", Error, "Action.ShowCard without a card"),
+    rule(
+        "invalid-card",
+        Error,
+        "Action.ShowCard card is not an object",
+    ),
+    rule(
+        "missing-target-elements",
+        Error,
+        "Action.ToggleVisibility without targetElements",
+    ),
+    rule(
+        "empty-target-elements",
+        Error,
+        "Action.ToggleVisibility with no targetElements",
+    ),
+    // Versioned schemas and compatibility. The bundled schemas approximate the published
+    // ones, so their findings are advisory.
+    rule(
+        "unsupported-version",
+        Warning,
+        "No schema for the declared version",
+    ),
+    rule(
+        "schema-missing-property",
+        Warning,
+        "Required property missing",
+    ),
+    rule(
+        "schema-invalid-type",
+        Warning,
+        "Property has the wrong JSON type",
+    ),
+    rule(
+        "schema-unknown-type",
+        Warning,
+        "Unknown element or action type",
+    ),
+    rule(
+        "schema-invalid-value",
+        Warning,
+        "Property value not allowed",
+    ),
+    rule("schema-violation", Warning, "Other schema violation"),
+    rule(
+        "requires-newer-version",
+        Warning,
+        "Construct newer than the declared version",
+    ),
+    // Host profiles.
+    rule(
+        "host-unsupported-version",
+        Error,
+        "Card version above the host maximum",
+    ),
+    rule(
+        "host-unsupported-element",
+        Error,
+        "Element the host cannot render",
+    ),
+    rule(
+        "host-unsupported-action",
+        Error,
+        "Action the host cannot run",
+    ),
+    rule(
+        "host-payload-too-large",
+        Error,
+        "Card larger than the host payload limit",
+    ),
+    rule(
+        "payload-too-large",
+        Error,
+        "Card larger than max_payload_bytes",
+    ),
+    // Id references.
+    rule("duplicate-element-id", Error, "Two elements share an id"),
+    rule(
+        "unknown-id-reference",
+        Error,
+        "Reference to an undeclared id",
+    ),
+    rule(
+        "invalid-toggle-target",
+        Error,
+        "Toggle target is an input or action",
+    ),
+    rule(
+        "invalid-input-reference",
+        Error,
+        "Input reference names a non-input",
+    ),
+    rule(
+        "invalid-input-association",
+        Warning,
+        "Input label or errorMessage without a usable, unique input id",
+    ),
+    // URLs.
+    rule(
+        "invalid-url",
+        Error,
+        "URL that is not absolute and well formed",
+    ),
+    rule(
+        "unsafe-url-scheme",
+        Error,
+        "javascript:, vbscript:, file: or non-image data: URL",
+    ),
+    rule("url-host-denied", Error, "URL host on the denylist"),
+    rule(
+        "url-host-not-allowed",
+        Error,
+        "URL host missing from the allowlist",
+    ),
+    rule(
+        "untrusted-url",
+        Warning,
+        "URL bound from payload or session data",
+    ),
+    // Bindings.
+    rule(
+        "missing-binding",
+        Warning,
+        "Binding left unresolved under a lenient binding_policy",
+    ),
+    rule(
+        "ambiguous-binding-path",
+        Warning,
+        "Bare path found in more than one root",
+    ),
+    rule(
+        "missing-translation",
+        Warning,
+        "String key missing from every table",
+    ),
+    // Accessibility.
+    rule(
+        "a11y-image-alt-text",
+        Warning,
+        "Image (including ImageSet images) without altText",
+    ),
+    rule("a11y-input-label", Warning, "Input without label"),
+    rule(
+        "a11y-required-error-message",
+        Warning,
+        "isRequired input without errorMessage",
+    ),
+    rule("a11y-action-title", Warning, "Action without title"),
+    rule(
+        "a11y-icon-only-action",
+        Warning,
+        "Action whose title is empty or only symbols next to an icon",
+    ),
+    rule(
+        "a11y-low-contrast",
+        Warning,
+        "Text color that blends into the container style behind it",
+    ),
+    rule(
+        "a11y-heading-style",
+        Info,
+        "Heading style on body text, or heading-like text without the heading style",
+    ),
+    rule(
+        "a11y-media-captions",
+        Warning,
+        "Media without captionSources",
+    ),
+];
+
+/// Every card validation rule with its default severity.
+pub fn validation_rules() -> &'static [ValidationRule] {
+    CATALOGUE
+}
+
+/// Sets each issue's severity from `levels` (keyed by code), falling back to the rule's
+/// default, and drops issues whose rule is `off`. Codes outside the catalogue keep the
+/// severity they were raised with.
+pub(crate) fn apply(
+    issues: Vec<ValidationIssue>,
+    levels: &BTreeMap<String, RuleLevel>,
+) -> Vec<ValidationIssue> {
+    issues
+        .into_iter()
+        .filter_map(|mut issue| {
+            issue.severity = match levels.get(&issue.code) {
+                Some(RuleLevel::Off) => return None,
+                Some(RuleLevel::Error) => Error,
+                Some(RuleLevel::Warning) => Warning,
+                Some(RuleLevel::Info) => Info,
+                None => CATALOGUE
+                    .iter()
+                    .find(|rule| rule.code == issue.code)
+                    .map_or(issue.severity, |rule| rule.severity),
+            };
+            Some(issue)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(code: &str) -> ValidationIssue {
+        ValidationIssue {
+            code: code.into(),
+            path: "/".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn applies_defaults_and_configured_levels() {
+        let issues = vec![
+            issue("missing-title"),
+            issue("missing-url"),
+            issue("a11y-media-captions"),
+            issue("a11y-heading-style"),
+            issue("custom-host-rule"),
+        ];
+        let levels = BTreeMap::from([
+            ("missing-url".to_string(), RuleLevel::Warning),
+            ("a11y-media-captions".to_string(), RuleLevel::Off),
+        ]);
+        let applied: Vec<(String, Severity)> = apply(issues, &levels)
+            .into_iter()
+            .map(|issue| (issue.code, issue.severity))
+            .collect();
+        assert_eq!(
+            applied,
+            [
+                ("missing-title".to_string(), Warning),
+                ("missing-url".to_string(), Warning),
+                ("a11y-heading-style".to_string(), Info),
+                ("custom-host-rule".to_string(), Error),
+            ]
+        );
+
+        let mut codes: Vec<&str> = CATALOGUE.iter().map(|rule| rule.code).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), CATALOGUE.len(), "rule codes are unique");
+    }
+}
@@ -259,9 +259,11 @@ fn is_not_found(code: &str) -> bool {
 mod tests {
     use super::*;
     use crate::model::{
-        AdaptiveCardInvocation, CardSource, CardSpec, InvocationMode, ValidationMode,
+        AdaptiveCardInvocation, BindingPolicy, CardSource, CardSpec, EscapePolicy, HandlebarsMode,
+        InvocationMode, ValidationMode,
     };
     use serde_json::json;
+    use std::collections::BTreeMap;
 
     fn base_invocation() -> AdaptiveCardInvocation {
         AdaptiveCardInvocation {
@@ -277,6 +279,19 @@ mod tests {
             interaction: None,
             mode: InvocationMode::RenderAndValidate,
             validation_mode: ValidationMode::Warn,
+            binding_policy: BindingPolicy::Error,
+            binding_diagnostics: false,
+            strict_paths: false,
+            root_precedence: Vec::new(),
+            handlebars_mode: HandlebarsMode::Text,
+            escape_policy: EscapePolicy::None,
+            host_profile: None,
+            downgrade: false,
+            target_version: None,
+            rules: BTreeMap::new(),
+            max_payload_bytes: None,
+            minify: false,
+            url_policy: Default::default(),
             envelope: None,
         }
     }
@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use handlebars::Handlebars;
+use once_cell::sync::Lazy;
+
+use crate::error::ComponentError;
+
+const DEFAULT_CAPACITY: usize = 128;
+
+static TEMPLATE_CACHE: Lazy<Mutex<TemplateCache>> =
+    Lazy::new(|| Mutex::new(TemplateCache::new(cache_capacity())));
+
+/// A Handlebars registry holding every template string (and partial) of one card,
+/// registered under its own source text.
+pub type CompiledCard = Handlebars<'static>;
+
+/// Cache counters reported in the trace event's `bindings_summary`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TemplateCacheStats {
+    /// Whether this invocation reused a cached registry.
+    pub hit: bool,
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// Returns the compiled templates cached under `hash` (the card content hash, combined
+/// with its partial sources), compiling them with `compile` on a miss. Cards without a hash
+/// are compiled without caching.
+pub(crate) fn compiled_card(
+    hash: Option<&str>,
+    compile: impl FnOnce() -> Result<CompiledCard, ComponentError>,
+) -> Result<(Arc<CompiledCard>, Option<TemplateCacheStats>), ComponentError> {
+    let Some(hash) = hash else {
+        return Ok((Arc::new(compile()?), None));
+    };
+    if let Some((compiled, stats)) = lock()?.get(hash) {
+        return Ok((compiled, Some(stats)));
+    }
+    // Compile outside the lock; a concurrent miss on the same card simply compiles twice.
+    let compiled = Arc::new(compile()?);
+    let stats = lock()?.insert(hash, compiled.clone());
+    Ok((compiled, Some(stats)))
+}
+
+fn lock() -> Result<std::sync::MutexGuard<'static, TemplateCache>, ComponentError> {
+    TEMPLATE_CACHE
+        .lock()
+        .map_err(|_| ComponentError::Binding("template cache poisoned".into()))
+}
+
+/// `ADAPTIVE_CARD_TEMPLATE_CACHE_SIZE` bounds the number of cached cards; `0` disables
+/// caching.
+fn cache_capacity() -> usize {
+    std::env::var("ADAPTIVE_CARD_TEMPLATE_CACHE_SIZE")
+        .ok()
+        .and_then(|raw| raw.trim().parse().ok())
+        .unwrap_or(DEFAULT_CAPACITY)
+}
+
+/// Least-recently-used map from card hash to compiled templates.
+struct TemplateCache {
+    capacity: usize,
+    entries: HashMap<String, (Arc<CompiledCard>, u64)>,
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl TemplateCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, hash: &str) -> Option<(Arc<CompiledCard>, TemplateCacheStats)> {
+        self.tick += 1;
+        let (compiled, last_used) = self.entries.get_mut(hash)?;
+        self.recency.remove(last_used);
+        self.recency.insert(self.tick, hash.to_string());
+        *last_used = self.tick;
+        let compiled = compiled.clone();
+        self.hits += 1;
+        Some((compiled, self.stats(true)))
+    }
+
+    fn insert(&mut self, hash: &str, compiled: Arc<CompiledCard>) -> TemplateCacheStats {
+        self.misses += 1;
+        if self.capacity > 0 {
+            self.tick += 1;
+            if let Some((_, last_used)) = self.entries.remove(hash) {
+                self.recency.remove(&last_used);
+            }
+            while self.entries.len() >= self.capacity {
+                let Some((_, oldest)) = self.recency.pop_first() else {
+                    break;
+                };
+                self.entries.remove(&oldest);
+            }
+            self.entries.insert(hash.to_string(), (compiled, self.tick));
+            self.recency.insert(self.tick, hash.to_string());
+        }
+        self.stats(false)
+    }
+
+    fn stats(&self, hit: bool) -> TemplateCacheStats {
+        TemplateCacheStats {
+            hit,
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compiled() -> Arc<CompiledCard> {
+        Arc::new(Handlebars::new())
+    }
+
+    #[test]
+    fn evicts_least_recently_used_card() {
+        let mut cache = TemplateCache::new(2);
+        cache.insert("a", compiled());
+        cache.insert("b", compiled());
+        assert!(cache.get("a").is_some());
+        let stats = cache.insert("c", compiled());
+        assert_eq!(stats.entries, 2);
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        let stats = cache.stats(true);
+        assert_eq!((stats.hits, stats.misses), (3, 3));
+    }
+
+    #[test]
+    fn zero_capacity_disables_caching() {
+        let mut cache = TemplateCache::new(0);
+        let stats = cache.insert("a", compiled());
+        assert_eq!(stats.entries, 0);
+        assert!(cache.get("a").is_none());
+    }
+}
@@ -49,7 +49,16 @@ pub fn build_trace_event(
             "handlebars_expansions": binding_summary.handlebars_expansions,
             "placeholder_replacements": binding_summary.placeholder_replacements,
             "expression_evaluations": binding_summary.expression_evaluations,
-            "missing_paths": binding_summary.missing_paths
+            "missing_paths": binding_summary.missing_paths,
+            "repeated_elements": binding_summary.repeated_elements,
+            "removed_elements": binding_summary.removed_elements,
+            "expression_engine": binding_summary.expression_engine,
+            "template_cache": binding_summary.template_cache.as_ref().map(|cache| serde_json::json!({
+                "hit": cache.hit,
+                "hits": cache.hits,
+                "misses": cache.misses,
+                "entries": cache.entries
+            }))
         }),
     );
     if let Some(interaction) = interaction {
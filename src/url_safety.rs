@@ -0,0 +1,423 @@
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+
+use crate::compatibility::{escape_pointer, implicit_type};
+use crate::model::{PatchOp, PatchOperation, UrlPolicy, ValidationIssue};
+
+/// Properties holding a URL.
+pub(crate) const URL_KEYS: &[&str] = &["url", "iconUrl", "backgroundImage", "poster"];
+
+/// Schemes that run code or reach the local machine when opened.
+const UNSAFE_SCHEMES: &[&str] = &["javascript", "vbscript", "file"];
+
+/// Checks every URL in the card outside action `data`: it must be absolute and well formed,
+/// must not use `javascript:`, `vbscript:` or `file:` (nor `data:` other than raster images
+/// in image positions), and its host must pass `policy`. URLs bound from untrusted data
+/// (at or under a pointer in `untrusted`) are flagged for review.
+pub(crate) fn validate_urls(
+    card: &Value,
+    policy: &UrlPolicy,
+    untrusted: &BTreeSet<String>,
+) -> Vec<ValidationIssue> {
+    let mut check = Check {
+        policy,
+        untrusted,
+        issues: Vec::new(),
+    };
+    if let Some(map) = card.as_object() {
+        check.visit(map, "AdaptiveCard", "");
+    }
+    check.issues
+}
+
+struct Check<'a> {
+    policy: &'a UrlPolicy,
+    untrusted: &'a BTreeSet<String>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl Check<'_> {
+    fn visit(&mut self, map: &Map<String, Value>, kind: &str, path: &str) {
+        for (key, child) in map {
+            if key == "data" {
+                continue;
+            }
+            let child_path = format!("{path}/{}", escape_pointer(key));
+            match child {
+                Value::String(url) if URL_KEYS.contains(&key.as_str()) => {
+                    let image = key != "url" || matches!(kind, "Image" | "BackgroundImage");
+                    self.check(url, image, &child_path);
+                }
+                _ => self.visit_child(child, kind, key, &child_path),
+            }
+        }
+    }
+
+    fn visit_child(&mut self, value: &Value, parent: &str, key: &str, path: &str) {
+        match value {
+            Value::Object(map) => {
+                let kind = map
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .or_else(|| implicit_type(parent, key))
+                    .unwrap_or(if key == "backgroundImage" {
+                        "BackgroundImage"
+                    } else {
+                        ""
+                    });
+                self.visit(map, kind, path);
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.visit_child(item, parent, key, &format!("{path}/{index}"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check(&mut self, url: &str, image: bool, path: &str) {
+        if url.is_empty() {
+            return;
+        }
+        if is_untrusted(path, self.untrusted) {
+            self.report(
+                "untrusted-url",
+                path,
+                format!("URL `{url}` is bound from untrusted payload or session data"),
+            );
+        }
+        let Some((scheme, rest)) = split_scheme(url) else {
+            self.report(
+                "invalid-url",
+                path,
+                format!("`{url}` is not an absolute URL"),
+            );
+            return;
+        };
+        if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            self.report(
+                "invalid-url",
+                path,
+                format!("`{url}` contains whitespace or control characters"),
+            );
+            return;
+        }
+        if UNSAFE_SCHEMES.contains(&scheme.as_str()) {
+            self.report(
+                "unsafe-url-scheme",
+                path,
+                format!("`{scheme}:` URLs are not allowed"),
+            );
+            return;
+        }
+        if scheme == "data" {
+            let media_type = rest.split([';', ',']).next().unwrap_or_default();
+            let media_type = media_type.to_ascii_lowercase();
+            if !image || !media_type.starts_with("image/") || media_type == "image/svg+xml" {
+                self.report(
+                    "unsafe-url-scheme",
+                    path,
+                    "`data:` URLs are only allowed for raster images".into(),
+                );
+            }
+            return;
+        }
+
+        let Some(authority) = rest.strip_prefix("//") else {
+            if matches!(scheme.as_str(), "http" | "https") {
+                self.report("invalid-url", path, format!("`{url}` has no host"));
+            }
+            return;
+        };
+        let Some(host) = host_of(authority) else {
+            self.report("invalid-url", path, format!("`{url}` has no host"));
+            return;
+        };
+        if self
+            .policy
+            .denied_hosts
+            .iter()
+            .any(|entry| host_matches(&host, entry))
+        {
+            self.report(
+                "url-host-denied",
+                path,
+                format!("host `{host}` is denied by the URL policy"),
+            );
+        } else if !self.policy.allowed_hosts.is_empty()
+            && !self
+                .policy
+                .allowed_hosts
+                .iter()
+                .any(|entry| host_matches(&host, entry))
+        {
+            self.report(
+                "url-host-not-allowed",
+                path,
+                format!("host `{host}` is not in the URL allowlist"),
+            );
+        }
+    }
+
+    fn report(&mut self, code: &str, path: &str, message: String) {
+        self.issues.push(ValidationIssue {
+            code: code.into(),
+            message,
+            path: path.to_string(),
+            ..Default::default()
+        });
+    }
+}
+
+/// Whether the value at `path`, or a value containing it, came from untrusted data.
+fn is_untrusted(path: &str, untrusted: &BTreeSet<String>) -> bool {
+    let mut path = path;
+    loop {
+        if untrusted.contains(path) {
+            return true;
+        }
+        match path.rfind('/') {
+            Some(index) if index > 0 => path = &path[..index],
+            _ => return false,
+        }
+    }
+}
+
+/// A node a pass kept at `to`, or removed (`None`), that was at `from` before it.
+#[derive(Debug)]
+pub(crate) struct NodeMove {
+    pub(crate) from: String,
+    pub(crate) to: Option<String>,
+}
+
+/// Pointers of card values that came from untrusted data. They are kept beside the card,
+/// never in it, and follow its nodes as later passes move, repeat or remove them.
+#[derive(Debug, Default)]
+pub(crate) struct UntrustedPointers(BTreeSet<String>);
+
+impl UntrustedPointers {
+    pub(crate) fn insert(&mut self, pointer: String) {
+        self.0.insert(pointer);
+    }
+
+    pub(crate) fn extend(&mut self, other: UntrustedPointers) {
+        self.0.extend(other.0);
+    }
+
+    pub(crate) fn pointers(&self) -> &BTreeSet<String> {
+        &self.0
+    }
+
+    /// Moves each pointer with the node that contained it most closely before the pass, to
+    /// every place that node was kept (a repeated node is kept more than once). Pointers in
+    /// removed nodes, or in no recorded node, are dropped.
+    pub(crate) fn relocate(&mut self, moves: &[NodeMove]) {
+        let mut relocated = BTreeSet::new();
+        for pointer in &self.0 {
+            let Some(depth) = moves
+                .iter()
+                .filter_map(|node| relative(pointer, &node.from).map(|_| node.from.len()))
+                .max()
+            else {
+                continue;
+            };
+            for node in moves.iter().filter(|node| node.from.len() == depth) {
+                if let (Some(rest), Some(to)) = (relative(pointer, &node.from), &node.to) {
+                    relocated.insert(format!("{to}{rest}"));
+                }
+            }
+        }
+        self.0 = relocated;
+    }
+
+    /// Follows JSON Patch operations as they are applied to the card. Replaced and removed
+    /// values are dropped; array insertions and removals shift the later items.
+    pub(crate) fn follow_patch(&mut self, patch: &[PatchOperation]) {
+        for operation in patch {
+            let path = operation.path.as_str();
+            if operation.op != PatchOp::Add {
+                self.0.retain(|pointer| relative(pointer, path).is_none());
+            }
+            let Some((parent, last)) = path.rsplit_once('/') else {
+                continue;
+            };
+            let Ok(index) = last.parse::<usize>() else {
+                continue;
+            };
+            let shift = |position: usize| match operation.op {
+                PatchOp::Add if position >= index => Some(position + 1),
+                PatchOp::Remove if position > index => Some(position - 1),
+                _ => None,
+            };
+            self.0 = std::mem::take(&mut self.0)
+                .into_iter()
+                .map(|pointer| {
+                    let Some(rest) = pointer
+                        .strip_prefix(parent)
+                        .and_then(|r| r.strip_prefix('/'))
+                    else {
+                        return pointer;
+                    };
+                    let (segment, tail) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+                    match segment.parse::<usize>().ok().and_then(shift) {
+                        Some(position) => format!("{parent}/{position}{tail}"),
+                        None => pointer,
+                    }
+                })
+                .collect();
+        }
+    }
+}
+
+/// The rest of `pointer` below `prefix` (empty when they are equal), if it is inside it.
+fn relative<'p>(pointer: &'p str, prefix: &str) -> Option<&'p str> {
+    let rest = pointer.strip_prefix(prefix)?;
+    (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+/// Lowercased scheme and the rest after `:`, for a syntactically valid scheme.
+fn split_scheme(url: &str) -> Option<(String, &str)> {
+    let (scheme, rest) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    (valid && !rest.is_empty()).then(|| (scheme.to_ascii_lowercase(), rest))
+}
+
+/// Host of an authority (`user@host:port`), lowercased, without brackets or a trailing dot.
+fn host_of(authority: &str) -> Option<String> {
+    let authority = authority.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host_port.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// `entry` names the host itself or a parent domain (`example.com` matches `cdn.example.com`).
+fn host_matches(host: &str, entry: &str) -> bool {
+    let entry = entry
+        .trim()
+        .trim_start_matches("*.")
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    !entry.is_empty()
+        && (host == entry
+            || host
+                .strip_suffix(&entry)
+                .is_some_and(|prefix| prefix.ends_with('.')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flags_unsafe_and_disallowed_urls() {
+        let card = json!({
+            "type": "AdaptiveCard",
+            "version": "1.5",
+            "backgroundImage": { "url": "data:image/png;base64,AAAA" },
+            "body": [
+                { "type": "Image", "url": "https://cdn.example.com/a.png" },
+                { "type": "Image", "url": "assets/b.png" },
+                { "type": "Media", "poster": "data:image/svg+xml,<svg/>", "sources": [
+                    { "mimeType": "video/mp4", "url": "file:///etc/passwd" }
+                ] },
+                { "type": "Container", "items": [], "selectAction": {
+                    "type": "Action.OpenUrl", "url": "https://tracker.example.com/x"
+                } }
+            ],
+            "actions": [
+                { "type": "Action.OpenUrl", "url": "JavaScript:alert(1)" },
+                { "type": "Action.OpenUrl", "url": "https://evil.test/phish" },
+                { "type": "Action.OpenUrl", "url": "data:text/html,<b>hi</b>" },
+                { "type": "Action.Submit", "data": { "url": "javascript:ignored" } }
+            ]
+        });
+        let policy = UrlPolicy {
+            allowed_hosts: vec!["example.com".into()],
+            denied_hosts: vec!["tracker.example.com".into()],
+        };
+        let untrusted = BTreeSet::from(["/actions/1/url".to_string(), "/body/3".to_string()]);
+        let issues: Vec<(String, String)> = validate_urls(&card, &policy, &untrusted)
+            .into_iter()
+            .map(|issue| (issue.code, issue.path))
+            .collect();
+        let expected = [
+            ("unsafe-url-scheme", "/actions/0/url"),
+            ("untrusted-url", "/actions/1/url"),
+            ("url-host-not-allowed", "/actions/1/url"),
+            ("unsafe-url-scheme", "/actions/2/url"),
+            ("invalid-url", "/body/1/url"),
+            ("unsafe-url-scheme", "/body/2/poster"),
+            ("unsafe-url-scheme", "/body/2/sources/0/url"),
+            ("untrusted-url", "/body/3/selectAction/url"),
+            ("url-host-denied", "/body/3/selectAction/url"),
+        ];
+        assert_eq!(
+            issues,
+            expected.map(|(code, path)| (code.to_string(), path.to_string()))
+        );
+    }
+
+    #[test]
+    fn untrusted_pointers_follow_moved_nodes() {
+        let mut untrusted = UntrustedPointers::default();
+        for pointer in [
+            "/body/0/url",
+            "/body/1/url",
+            "/body/2/fallback/url",
+            "/actions/1/url",
+        ] {
+            untrusted.insert(pointer.to_string());
+        }
+        let moved = |from: &str, to: Option<&str>| NodeMove {
+            from: from.into(),
+            to: to.map(str::to_string),
+        };
+        // `/body/0` repeated twice, `/body/1` dropped, `/body/2` replaced by its fallback.
+        untrusted.relocate(&[
+            moved("", Some("")),
+            moved("/body/0", Some("/body/0")),
+            moved("/body/0", Some("/body/1")),
+            moved("/body/1", None),
+            moved("/body/2", None),
+            moved("/body/2/fallback", Some("/body/2")),
+        ]);
+        untrusted.follow_patch(&[PatchOperation {
+            op: PatchOp::Remove,
+            path: "/actions/0".into(),
+            value: None,
+        }]);
+        let pointers: Vec<&str> = untrusted.pointers().iter().map(String::as_str).collect();
+        assert_eq!(
+            pointers,
+            [
+                "/actions/0/url",
+                "/body/0/url",
+                "/body/1/url",
+                "/body/2/url"
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_hosts() {
+        assert_eq!(
+            host_of("user:pw@Example.COM.:8443/path").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(host_of("[::1]:80").as_deref(), Some("::1"));
+        assert_eq!(host_of("/path"), None);
+        assert!(host_matches("a.example.com", "*.example.com"));
+        assert!(!host_matches("badexample.com", "example.com"));
+    }
+}
@@ -4,6 +4,7 @@ use serde_json::Value;
 use jsonschema::error::ValidationErrorKind;
 use jsonschema::{Validator, draft7};
 
+use crate::compatibility;
 use crate::model::ValidationIssue;
 
 static INVOCATION_SCHEMA: Lazy<Validator> = Lazy::new(|| {
@@ -16,6 +17,30 @@ static INVOCATION_SCHEMA: Lazy<Validator> = Lazy::new(|| {
         .expect("invocation schema must compile")
 });
 
+/// Adaptive Card schemas for versions 1.0 through 1.6, indexed by minor version.
+const CARD_SCHEMA_SOURCES: [&str; 7] = [
+    include_str!("../schemas/cards/adaptive-card-1.0.schema.json"),
+    include_str!("../schemas/cards/adaptive-card-1.1.schema.json"),
+    include_str!("../schemas/cards/adaptive-card-1.2.schema.json"),
+    include_str!("../schemas/cards/adaptive-card-1.3.schema.json"),
+    include_str!("../schemas/cards/adaptive-card-1.4.schema.json"),
+    include_str!("../schemas/cards/adaptive-card-1.5.schema.json"),
+    include_str!("../schemas/cards/adaptive-card-1.6.schema.json"),
+];
+
+static CARD_SCHEMAS: Lazy<Vec<Validator>> = Lazy::new(|| {
+    CARD_SCHEMA_SOURCES
+        .iter()
+        .map(|source| {
+            let schema: Value =
+                serde_json::from_str(source).expect("card schema JSON must be valid");
+            draft7::options()
+                .build(&schema)
+                .expect("card schema must compile")
+        })
+        .collect()
+});
+
 pub fn locate_invocation_candidate(value: &Value) -> Option<Value> {
     if let Some(inv) = find_invocation_value(value) {
         return Some(inv);
@@ -57,6 +82,59 @@ fn map_schema_error(error: &jsonschema::ValidationError) -> ValidationIssue {
         code: code.to_string(),
         message: error.to_string(),
         path,
+        ..Default::default()
+    }
+}
+
+/// Validates a card against the schema of its declared `version`. A card without a
+/// version is left to the structural checks; an unknown version is reported as
+/// `unsupported-version`.
+pub fn validate_card_schema(card: &Value) -> Vec<ValidationIssue> {
+    let Some(version) = card.get("version") else {
+        return Vec::new();
+    };
+    let Some(minor) = version
+        .as_str()
+        .and_then(compatibility::parse_version)
+        .map(|minor| minor as usize)
+        .filter(|minor| *minor < CARD_SCHEMAS.len())
+    else {
+        return vec![ValidationIssue {
+            code: "unsupported-version".into(),
+            message: format!(
+                "No Adaptive Card schema for version {version}; supported versions are 1.0 to 1.6"
+            ),
+            path: "/version".into(),
+            ..Default::default()
+        }];
+    };
+    CARD_SCHEMAS[minor]
+        .iter_errors(card)
+        .map(|error| map_card_schema_error(&error))
+        .collect()
+}
+
+fn map_card_schema_error(error: &jsonschema::ValidationError) -> ValidationIssue {
+    let code = match error.kind() {
+        ValidationErrorKind::Required { .. } => "schema-missing-property",
+        ValidationErrorKind::Type { .. } => "schema-invalid-type",
+        ValidationErrorKind::Enum { .. } => "schema-unknown-type",
+        ValidationErrorKind::Constant { .. }
+        | ValidationErrorKind::Pattern { .. }
+        | ValidationErrorKind::AnyOf { .. } => "schema-invalid-value",
+        _ => "schema-violation",
+    };
+    let raw_path = error.instance_path().to_string();
+    let path = if raw_path.is_empty() {
+        "/".to_string()
+    } else {
+        raw_path
+    };
+    ValidationIssue {
+        code: code.to_string(),
+        message: error.to_string(),
+        path,
+        ..Default::default()
     }
 }
 
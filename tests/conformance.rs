@@ -1,8 +1,12 @@
 use component_adaptive_card::{
-    AdaptiveCardInvocation, CardInteraction, CardInteractionType, CardSource, CardSpec,
-    InvocationMode, ValidationMode, handle_invocation, register_host_asset_callback,
+    AdaptiveCardInvocation, BindingPolicy, BindingRoot, CardInteraction, CardInteractionType,
+    CardSource, CardSpec, ComponentError, EscapePolicy, ExpressionEngine, HandlebarsMode,
+    HostProfile, InvocationMode, RuleLevel, Severity, ValidationMode, handle_invocation,
+    register_expression_engine, register_host_asset_callback, register_host_function,
+    validation_rules,
 };
 use serde_json::json;
+use std::collections::BTreeMap;
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs;
 
@@ -15,6 +19,9 @@ fn base_invocation(card: serde_json::Value) -> AdaptiveCardInvocation {
             catalog_name: None,
             template_params: None,
             asset_registry: None,
+            expression_engine: None,
+            strings: None,
+            default_locale: None,
         },
         node_id: None,
         payload: json!({}),
@@ -23,6 +30,19 @@ fn base_invocation(card: serde_json::Value) -> AdaptiveCardInvocation {
         interaction: None,
         mode: InvocationMode::RenderAndValidate,
         validation_mode: ValidationMode::Warn,
+        binding_policy: BindingPolicy::Error,
+        binding_diagnostics: false,
+        strict_paths: false,
+        root_precedence: Vec::new(),
+        handlebars_mode: HandlebarsMode::Text,
+        escape_policy: EscapePolicy::None,
+        host_profile: None,
+        downgrade: false,
+        target_version: None,
+        rules: BTreeMap::new(),
+        max_payload_bytes: None,
+        minify: false,
+        url_policy: Default::default(),
         envelope: None,
     }
 }
@@ -125,6 +145,19 @@ fn asset_render_loads_card() {
         interaction: None,
         mode: InvocationMode::RenderAndValidate,
         validation_mode: ValidationMode::Warn,
+        binding_policy: BindingPolicy::Error,
+        binding_diagnostics: false,
+        strict_paths: false,
+        root_precedence: Vec::new(),
+        handlebars_mode: HandlebarsMode::Text,
+        escape_policy: EscapePolicy::None,
+        host_profile: None,
+        downgrade: false,
+        target_version: None,
+        rules: BTreeMap::new(),
+        max_payload_bytes: None,
+        minify: false,
+        url_policy: Default::default(),
         envelope: None,
     };
 
@@ -166,6 +199,19 @@ fn catalog_resolution_uses_env_mapping() {
         interaction: None,
         mode: InvocationMode::RenderAndValidate,
         validation_mode: ValidationMode::Warn,
+        binding_policy: BindingPolicy::Error,
+        binding_diagnostics: false,
+        strict_paths: false,
+        root_precedence: Vec::new(),
+        handlebars_mode: HandlebarsMode::Text,
+        escape_policy: EscapePolicy::None,
+        host_profile: None,
+        downgrade: false,
+        target_version: None,
+        rules: BTreeMap::new(),
+        max_payload_bytes: None,
+        minify: false,
+        url_policy: Default::default(),
         envelope: None,
     };
 
@@ -213,6 +259,30 @@ fn bindings_apply_default_with_coalesce() {
     assert_eq!(text, "Hello Guest");
 }
 
+#[test]
+fn legacy_defaults_are_literals_rather_than_logical_or() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "${payload.name || Guest}" },
+            { "type": "Toggle", "id": "a", "value": "${payload.flag || true}" },
+            { "type": "Toggle", "id": "b", "value": "${payload.missing || true}" },
+            { "type": "TextBlock", "text": "${payload.name || payload.flag}" },
+            { "type": "TextBlock", "text": "${(payload.name || payload.fallback)}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "flag": false, "fallback": "Anon" });
+    let result = handle_invocation(invocation).expect("render with defaults");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Guest");
+    assert_eq!(rendered["body"][1]["value"], false);
+    assert_eq!(rendered["body"][2]["value"], true);
+    assert_eq!(rendered["body"][3]["text"], "payload.flag");
+    assert_eq!(rendered["body"][4]["text"], "Anon");
+}
+
 #[test]
 fn expression_placeholders_support_equality_and_ternary() {
     let card = json!({
@@ -390,6 +460,19 @@ fn host_asset_registry_resolves_assets() {
         interaction: None,
         mode: InvocationMode::RenderAndValidate,
         validation_mode: ValidationMode::Warn,
+        binding_policy: BindingPolicy::Error,
+        binding_diagnostics: false,
+        strict_paths: false,
+        root_precedence: Vec::new(),
+        handlebars_mode: HandlebarsMode::Text,
+        escape_policy: EscapePolicy::None,
+        host_profile: None,
+        downgrade: false,
+        target_version: None,
+        rules: BTreeMap::new(),
+        max_payload_bytes: None,
+        minify: false,
+        url_policy: Default::default(),
         envelope: None,
     };
 
@@ -397,3 +480,1293 @@ fn host_asset_registry_resolves_assets() {
     let card = result.rendered_card.expect("card should render");
     assert_eq!(card["type"], "AdaptiveCard");
 }
+
+#[test]
+fn data_binding_repeats_elements_per_item() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "Orders for ${customer}" },
+            {
+                "$data": "${payload.orders}",
+                "type": "TextBlock",
+                "text": "#${$index} ${id}: ${$data.total} (${$root.customer})"
+            },
+            {
+                "type": "FactSet",
+                "facts": [
+                    { "$data": "${payload.tags}", "title": "Tag", "value": "${$data}" }
+                ]
+            }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({
+        "customer": "Ada",
+        "orders": [ { "id": "A1", "total": 10 }, { "id": "B2", "total": 25 } ],
+        "tags": ["vip", "eu"]
+    });
+
+    let result = handle_invocation(invocation).expect("render with $data");
+    let rendered = result.rendered_card.expect("card should render");
+    let body = rendered["body"].as_array().expect("body array");
+    assert_eq!(body.len(), 4);
+    assert_eq!(body[0]["text"], "Orders for Ada");
+    assert_eq!(body[1]["text"], "#0 A1: 10 (Ada)");
+    assert_eq!(body[2]["text"], "#1 B2: 25 (Ada)");
+    assert!(body[1].get("$data").is_none());
+    assert_eq!(body[3]["facts"][1]["value"], "eu");
+}
+
+#[test]
+fn data_binding_object_rescopes_element() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            {
+                "type": "Container",
+                "$data": "${payload.ticket}",
+                "items": [ { "type": "TextBlock", "text": "${title}" } ]
+            },
+            { "$data": [], "type": "TextBlock", "text": "never rendered" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "ticket": { "title": "Printer on fire" } });
+
+    let result = handle_invocation(invocation).expect("render with $data object");
+    let rendered = result.rendered_card.expect("card should render");
+    let body = rendered["body"].as_array().expect("body array");
+    assert_eq!(body.len(), 1);
+    assert_eq!(body[0]["items"][0]["text"], "Printer on fire");
+}
+
+#[test]
+fn when_condition_drops_falsy_elements() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "Request from ${payload.requester}" },
+            { "type": "Image", "url": "https://example.com/a.png", "$when": "${payload.showImage}" },
+            {
+                "$data": "${payload.items}",
+                "$when": "${price == 0}",
+                "type": "TextBlock",
+                "text": "${name} is free"
+            }
+        ],
+        "actions": [
+            { "type": "Action.Submit", "title": "Approve", "$when": "${session.role == \"manager\"}" },
+            { "type": "Action.Submit", "title": "Comment" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({
+        "requester": "Ada",
+        "showImage": false,
+        "items": [ { "name": "Pen", "price": 0 }, { "name": "Desk", "price": 120 } ]
+    });
+    invocation.session = json!({ "role": "engineer" });
+
+    let result = handle_invocation(invocation).expect("render with $when");
+    let rendered = result.rendered_card.expect("card should render");
+    let body = rendered["body"].as_array().expect("body array");
+    assert_eq!(body.len(), 2);
+    assert_eq!(body[1]["text"], "Pen is free");
+    assert!(body[1].get("$when").is_none());
+    let actions = rendered["actions"].as_array().expect("actions array");
+    assert_eq!(actions.len(), 1);
+    assert_eq!(actions[0]["title"], "Comment");
+
+    assert!(
+        !result
+            .card_features
+            .used_elements
+            .contains(&"Image".to_string())
+    );
+    assert_eq!(
+        result.card_features.removed_elements,
+        vec![
+            "Action.Submit".to_string(),
+            "Image".to_string(),
+            "TextBlock".to_string()
+        ]
+    );
+
+    // The root has no parent to drop it from: its `$when` is evaluated inside its own
+    // `$data` scope, and a falsy one fails the render rather than being ignored.
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "$data": "${payload.order}",
+        "$when": "${open}",
+        "body": [ { "type": "TextBlock", "text": "Order ${id}" } ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "order": { "id": 7, "open": true } });
+    let result = handle_invocation(invocation.clone()).expect("open order renders");
+    let rendered = result.rendered_card.expect("card should render");
+    assert!(rendered.get("$when").is_none());
+    assert_eq!(rendered["body"][0]["text"], "Order 7");
+
+    invocation.payload = json!({ "order": { "id": 7, "open": false } });
+    let err = handle_invocation(invocation).expect_err("closed order has no card");
+    assert!(
+        err.to_string().contains("card-level $when is false"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn expressions_support_operators_inside_text() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            {
+                "type": "TextBlock",
+                "text": "Total ${payload.qty * payload.price + 1}, ${payload.qty >= 2 && payload.vip ? \"bulk:vip\" : \"standard\"} for ${payload.items[1].name}"
+            }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({
+        "qty": 3,
+        "price": 4,
+        "vip": true,
+        "items": [ { "name": "Pen" }, { "name": "Desk" } ]
+    });
+    let result = handle_invocation(invocation).expect("expression render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Total 13, bulk:vip for Desk");
+}
+
+#[test]
+fn expression_parse_errors_report_column() {
+    let input = json!({
+        "card_spec": {
+            "inline_json": {
+                "type": "AdaptiveCard",
+                "version": "1.6",
+                "body": [ { "type": "TextBlock", "text": "${payload.a == (1 + }" } ]
+            }
+        },
+        "payload": { "a": 1 }
+    });
+    let output = component_adaptive_card::handle_message("card", &input.to_string());
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(parsed["error"]["code"], "AC_BINDING_EVAL_ERROR");
+    let message = parsed["error"]["details"]["validation_issues"][0]["message"]
+        .as_str()
+        .expect("issue message");
+    assert!(
+        message.contains("column 18"),
+        "unexpected message: {message}"
+    );
+}
+
+#[test]
+fn expressions_call_builtin_functions() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            {
+                "type": "TextBlock",
+                "text": "${toUpper(payload.name)} has ${count(payload.orders)} orders, last on ${formatDateTime(last(payload.orders).date, 'd MMM yyyy')}"
+            },
+            {
+                "type": "TextBlock",
+                "text": "${join(sortBy(payload.tags), ', ')}"
+            }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({
+        "name": "Ada",
+        "tags": ["vip", "beta"],
+        "orders": [ { "date": "2024-03-01T10:00:00Z" }, { "date": "2024-05-17T08:30:00Z" } ]
+    });
+    let result = handle_invocation(invocation).expect("function render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(
+        rendered["body"][0]["text"],
+        "ADA has 2 orders, last on 17 May 2024"
+    );
+    assert_eq!(rendered["body"][1]["text"], "beta, vip");
+}
+
+#[test]
+fn host_functions_are_callable_from_expressions() {
+    register_host_function(
+        "maskEmail",
+        Box::new(|args| {
+            let email = args
+                .first()
+                .and_then(|v| v.as_str())
+                .ok_or("expects an email string")?;
+            let (user, domain) = email.split_once('@').ok_or("not an email address")?;
+            let visible: String = user.chars().take(1).collect();
+            Ok(json!(format!("{visible}***@{domain}")))
+        }),
+    )
+    .expect("register host function");
+    for reserved in ["t", "if"] {
+        assert_eq!(
+            register_host_function(reserved, Box::new(|_| Ok(json!(null)))),
+            Err("reserved function name")
+        );
+    }
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "TextBlock", "text": "Sent to ${maskEmail(payload.email)}" } ]
+    });
+    let mut invocation = base_invocation(card.clone());
+    invocation.payload = json!({ "email": "ada@example.com" });
+    let result = handle_invocation(invocation).expect("host function render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Sent to a***@example.com");
+
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "email": "nobody" });
+    let err = handle_invocation(invocation).expect_err("host function error");
+    assert!(
+        err.to_string()
+            .contains("maskEmail(): not an email address"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn unknown_functions_report_their_name() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "TextBlock", "text": "${currency(payload.amount, 'EUR')}" } ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "amount": 5 });
+    let err = handle_invocation(invocation).expect_err("unknown function");
+    assert!(
+        err.to_string().contains("unknown function `currency`"),
+        "unexpected error: {err}"
+    );
+}
+
+struct EchoEngine;
+
+impl ExpressionEngine for EchoEngine {
+    fn eval(
+        &self,
+        expr: &str,
+        _ctx: &component_adaptive_card::BindingContext,
+    ) -> Option<serde_json::Value> {
+        Some(json!(format!("<{expr}>")))
+    }
+}
+
+#[test]
+fn invocation_selects_expression_engine_by_name() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "TextBlock", "text": "${payload.total / payload.count}" } ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "total": 7, "count": 2 });
+    let rendered = handle_invocation(invocation.clone())
+        .expect("simple render")
+        .rendered_card
+        .expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "3.5");
+
+    invocation.card_spec.expression_engine = Some("adaptive".to_string());
+    let rendered = handle_invocation(invocation.clone())
+        .expect("adaptive render")
+        .rendered_card
+        .expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "3");
+
+    register_expression_engine("echo", Box::new(EchoEngine)).expect("register engine");
+    invocation.card_spec.expression_engine = Some("echo".to_string());
+    let rendered = handle_invocation(invocation.clone())
+        .expect("host engine render")
+        .rendered_card
+        .expect("card should render");
+    assert_eq!(
+        rendered["body"][0]["text"],
+        "<payload.total / payload.count>"
+    );
+
+    invocation.card_spec.expression_engine = Some("jsonata".to_string());
+    let err = handle_invocation(invocation).expect_err("unknown engine");
+    assert!(
+        err.to_string()
+            .contains("unknown expression engine `jsonata`"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn binding_policy_reports_missing_paths_as_issues() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "${payload.title}" },
+            { "type": "TextBlock", "text": "Hi @{payload.name}, ref ${payload.ref}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "name": "Ada" });
+    let err = handle_invocation(invocation.clone()).expect_err("error policy");
+    assert!(
+        err.to_string()
+            .contains("missing binding path: payload.title")
+    );
+
+    let cases = [
+        (BindingPolicy::Empty, json!(""), "Hi Ada, ref "),
+        (
+            BindingPolicy::KeepPlaceholder,
+            json!("${payload.title}"),
+            "Hi Ada, ref ${payload.ref}",
+        ),
+        (BindingPolicy::Null, json!(null), "Hi Ada, ref "),
+    ];
+    for (policy, title, text) in cases {
+        invocation.binding_policy = policy.clone();
+        let result = handle_invocation(invocation.clone()).expect("lenient policy");
+        let rendered = result.rendered_card.expect("card should render");
+        assert_eq!(rendered["body"][0]["text"], title, "{policy:?}");
+        assert_eq!(rendered["body"][1]["text"], text, "{policy:?}");
+        let missing: Vec<_> = result
+            .validation_issues
+            .iter()
+            .filter(|issue| issue.code == "missing-binding")
+            .map(|issue| issue.path.as_str())
+            .collect();
+        assert_eq!(missing, ["/body/0/text", "/body/1/text"], "{policy:?}");
+    }
+
+    invocation.binding_policy = BindingPolicy::Empty;
+    invocation.validation_mode = ValidationMode::Error;
+    let result = handle_invocation(invocation).expect("lenient policy in error mode");
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .all(|issue| issue.severity == Severity::Warning)
+    );
+}
+
+#[test]
+fn binding_diagnostics_report_provenance() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "${title}" },
+            { "type": "TextBlock", "text": "@{locale} / ${payload.count + 1} @{nickname||Guest}" },
+            { "type": "TextBlock", "text": "${nickname || 'anon'}", "$when": "${state.show}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "title": "Report", "count": 2 });
+    invocation.session = json!({ "locale": "nl-NL" });
+    invocation.state = json!({ "show": true });
+    let result = handle_invocation(invocation.clone()).expect("render");
+    assert!(result.binding_diagnostics.is_none());
+
+    invocation.binding_diagnostics = true;
+    let result = handle_invocation(invocation).expect("render with diagnostics");
+    let diagnostics = result.binding_diagnostics.expect("diagnostics requested");
+    assert_eq!(diagnostics.placeholder_replacements, 3);
+    let entries: Vec<_> = diagnostics
+        .bindings
+        .iter()
+        .map(|b| {
+            (
+                b.pointer.as_str(),
+                b.expression.as_str(),
+                b.roots.join(","),
+                b.value_type.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("/body/0/text", "title", "payload".to_string(), "string"),
+            ("/body/1/text", "locale", "session".to_string(), "string"),
+            (
+                "/body/1/text",
+                "payload.count + 1",
+                "payload".to_string(),
+                "number"
+            ),
+            (
+                "/body/1/text",
+                "nickname||Guest",
+                "default".to_string(),
+                "string"
+            ),
+            ("/body/2", "state.show", "state".to_string(), "boolean"),
+            (
+                "/body/2/text",
+                "nickname || 'anon'",
+                String::new(),
+                "string"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn bare_paths_follow_precedence_and_strict_mode() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "TextBlock", "text": "Status: ${status}" } ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "status": "from payload" });
+    invocation.state = json!({ "status": "from state" });
+
+    let result = handle_invocation(invocation.clone()).expect("default precedence");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Status: from payload");
+    let ambiguous = result
+        .validation_issues
+        .iter()
+        .find(|issue| issue.code == "ambiguous-binding-path")
+        .expect("ambiguity warning");
+    assert_eq!(ambiguous.path, "/body/0/text");
+    assert!(ambiguous.message.contains("payload, state"));
+    assert_eq!(ambiguous.severity, Severity::Warning);
+
+    invocation.root_precedence = vec![BindingRoot::State, BindingRoot::Payload];
+    let result = handle_invocation(invocation.clone()).expect("state first");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Status: from state");
+
+    invocation.root_precedence = vec![BindingRoot::Session];
+    let err = handle_invocation(invocation.clone()).expect_err("root not searched");
+    assert!(err.to_string().contains("missing binding path: status"));
+
+    invocation.root_precedence = Vec::new();
+    invocation.strict_paths = true;
+    let err = handle_invocation(invocation).expect_err("strict paths");
+    assert!(
+        err.to_string()
+            .contains("unqualified binding path `status` is not allowed"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn handlebars_helpers_and_partials_render() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "{{> header}}" },
+            {
+                "type": "TextBlock",
+                "text": "{{count}} {{pluralize count \"order\"}} · {{formatNumber total 2}} · {{formatDate placed \"d MMM yyyy\"}}"
+            },
+            {
+                "type": "TextBlock",
+                "text": "{{truncate note 12}} / {{default nickname \"Guest\"}} / {{#if (and vip (gt count 1))}}bulk{{/if}}"
+            },
+            { "type": "TextBlock", "text": "{{{json tags}}}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.card_spec.asset_registry = Some(
+        [
+            ("header", "tests/assets/partials/header.hbs"),
+            ("badge", "tests/assets/partials/badge.hbs"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect(),
+    );
+    invocation.payload = json!({ "company": "Acme", "vip": true });
+    invocation.state = json!({
+        "input": {
+            "count": 3,
+            "total": 1234.5,
+            "placed": "2024-05-17T08:30:00Z",
+            "note": "Deliver to the back door",
+            "vip": true,
+            "tags": ["a", "b"]
+        }
+    });
+    let result = handle_invocation(invocation).expect("helpers render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Acme · VIP");
+    assert_eq!(
+        rendered["body"][1]["text"],
+        "3 orders · 1,234.50 · 17 May 2024"
+    );
+    assert_eq!(rendered["body"][2]["text"], "Deliver to … / Guest / bulk");
+    assert_eq!(rendered["body"][3]["text"], "[\"a\",\"b\"]");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn edited_partial_is_not_served_from_template_cache() {
+    let path = std::env::temp_dir().join(format!("ac-partial-{}.hbs", std::process::id()));
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "TextBlock", "text": "{{> signature}}" } ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.card_spec.asset_registry = Some(
+        [("signature".to_string(), path.display().to_string())]
+            .into_iter()
+            .collect(),
+    );
+    let render = |source: &str| {
+        fs::write(&path, source).expect("write partial");
+        let result = handle_invocation(invocation.clone()).expect("render");
+        result.rendered_card.expect("card")["body"][0]["text"].clone()
+    };
+    assert_eq!(render("Regards"), "Regards");
+    assert_eq!(render("Cheers"), "Cheers");
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn missing_handlebars_partial_is_reported() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "TextBlock", "text": "{{> footer-does-not-exist}}" } ]
+    });
+    let err = handle_invocation(base_invocation(card)).expect_err("missing partial");
+    assert!(
+        err.to_string().contains("partial footer-does-not-exist"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn cached_templates_render_each_invocation_context() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "Hello {{payload.name}}" },
+            { "type": "TextBlock", "text": "No templates here" }
+        ]
+    });
+    for name in ["Ada", "Grace", "Ada"] {
+        let mut invocation = base_invocation(card.clone());
+        invocation.payload = json!({ "name": name });
+        let result = handle_invocation(invocation).expect("card renders");
+        let rendered = result.rendered_card.expect("card should render");
+        assert_eq!(rendered["body"][0]["text"], format!("Hello {name}"));
+        assert_eq!(rendered["body"][1]["text"], "No templates here");
+    }
+}
+
+#[test]
+fn typed_handlebars_mode_preserves_json_values() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "{{payload.count}} items", "maxLines": "{{payload.count}}" },
+            { "type": "TextBlock", "text": "{{default payload.nickname \"Guest\"}}", "wrap": "{{ payload.wrap }}" },
+            { "type": "TextBlock", "text": "{{#if payload.wrap}}wrapped{{/if}}" },
+            {
+                "type": "Input.ChoiceSet",
+                "id": "size",
+                "choices": "{{{json payload.choices}}}"
+            }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.handlebars_mode = HandlebarsMode::Typed;
+    invocation.payload = json!({
+        "count": 3,
+        "wrap": true,
+        "choices": [
+            { "title": "Small", "value": "s" },
+            { "title": "Large", "value": "l" }
+        ]
+    });
+    let result = handle_invocation(invocation).expect("typed render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "3 items");
+    assert_eq!(rendered["body"][0]["maxLines"], 3);
+    assert_eq!(rendered["body"][1]["text"], "Guest");
+    assert_eq!(rendered["body"][1]["wrap"], true);
+    assert_eq!(rendered["body"][2]["text"], "wrapped");
+    assert_eq!(rendered["body"][3]["choices"][1]["value"], "l");
+}
+
+#[test]
+fn escape_policy_escapes_untrusted_markdown() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "**Hi** ${payload.name}" },
+            { "type": "TextBlock", "text": "@{payload.bio}" },
+            { "type": "TextBlock", "text": "${raw:payload.name} / ${state.note}" },
+            {
+                "type": "FactSet",
+                "facts": [ { "title": "Name", "value": "${session.display}" } ]
+            }
+        ],
+        "actions": [ { "type": "Action.OpenUrl", "title": "Open", "url": "${payload.link}" } ]
+    });
+    let mut invocation = base_invocation(card.clone());
+    invocation.escape_policy = EscapePolicy::Markdown;
+    invocation.payload = json!({
+        "name": "[click](http://evil)",
+        "bio": "- one\n2. two <b>",
+        "link": "https://example.com/a_(b)"
+    });
+    invocation.session = json!({ "display": "*Ada*" });
+    invocation.state = json!({ "note": "_trusted_" });
+    let result = handle_invocation(invocation.clone()).expect("markdown render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(
+        rendered["body"][0]["text"],
+        "**Hi** \\[click\\]\\(http://evil\\)"
+    );
+    assert_eq!(rendered["body"][1]["text"], "\\- one\n2\\. two <b>");
+    assert_eq!(
+        rendered["body"][2]["text"],
+        "[click](http://evil) / _trusted_"
+    );
+    assert_eq!(rendered["body"][3]["facts"][0]["value"], "\\*Ada\\*");
+    assert_eq!(rendered["actions"][0]["url"], "https://example.com/a_(b)");
+
+    invocation.escape_policy = EscapePolicy::Strict;
+    let result = handle_invocation(invocation).expect("strict render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][1]["text"], "\\- one 2\\. two &lt;b&gt;");
+}
+
+#[test]
+fn string_tables_follow_locale_fallback_chain() {
+    let mut invocation = base_invocation(json!({}));
+    invocation.card_source = CardSource::Asset;
+    invocation.card_spec = CardSpec {
+        asset_path: Some("tests/assets/cards/order.json".to_string()),
+        ..Default::default()
+    };
+    invocation.payload = json!({ "id": "A-17", "count": 3 });
+    invocation.session = json!({ "locale": "nl-BE" });
+    let result = handle_invocation(invocation.clone()).expect("localized render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Bestelling A-17");
+    assert_eq!(rendered["body"][1]["text"], "3 artikelen");
+    assert_eq!(rendered["body"][2]["text"], "Thanks for your order");
+    assert_eq!(rendered["body"][3]["text"], "order.unknown");
+    let issue = result
+        .validation_issues
+        .iter()
+        .find(|issue| issue.code == "missing-translation")
+        .expect("missing key is reported");
+    assert_eq!(issue.path, "/body/3/text");
+    assert!(issue.message.contains("nl-BE, nl, en"), "{}", issue.message);
+
+    invocation.session = json!({ "locale": "fr" });
+    invocation.payload = json!({ "id": "B-2", "count": 1 });
+    let result = handle_invocation(invocation).expect("fallback render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "Order B-2");
+    assert_eq!(rendered["body"][1]["text"], "1 item");
+}
+
+#[test]
+fn formatting_follows_session_locale_and_time_zone() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "text": "${formatDate(payload.due, 'long')} ${formatTime(payload.due)}" },
+            { "type": "TextBlock", "text": "${formatCurrency(payload.total, 'EUR')} / ${formatPercent(payload.share, 1)}" },
+            { "type": "TextBlock", "text": "${formatDuration(payload.eta)}" },
+            { "type": "TextBlock", "text": "Due {{DATE(2024-07-01T22:15:00Z, LONG)}} {{TIME(2024-07-01T22:15:00Z)}}" },
+            { "type": "TextBlock", "text": "{{formatCurrency payload.total \"EUR\"}} at {{formatTime payload.due}}, {{DATE(2024-07-01T22:15:00Z)}}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({
+        "due": "2024-07-01T22:15:00Z",
+        "total": 1234.5,
+        "share": 0.125,
+        "eta": "PT2H5M"
+    });
+    invocation.session = json!({ "locale": "de-DE", "timezone": "Europe/Berlin" });
+    let result = handle_invocation(invocation.clone()).expect("formatted render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "2. Juli 2024 00:15");
+    assert_eq!(rendered["body"][1]["text"], "1.234,50\u{a0}€ / 12,5\u{a0}%");
+    assert_eq!(rendered["body"][2]["text"], "2\u{a0}Stunden 5\u{a0}Minuten");
+    assert_eq!(
+        rendered["body"][3]["text"],
+        "Due Dienstag, 2. Juli 2024 00:15"
+    );
+    assert_eq!(
+        rendered["body"][4]["text"],
+        "1.234,50\u{a0}€ at 00:15, 02.07.2024"
+    );
+
+    invocation.session = json!({ "locale": "en-US", "timezone": "America/New_York" });
+    let result = handle_invocation(invocation).expect("formatted render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["body"][0]["text"], "July 1, 2024 6:15 PM");
+    assert_eq!(
+        rendered["body"][3]["text"],
+        "Due Monday, July 1, 2024 6:15 PM"
+    );
+}
+
+#[test]
+fn schema_validation_follows_card_version() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.0",
+        "body": [
+            { "type": "TextBlock", "text": "Hi", "size": "Large", "weight": "Heavy" },
+            { "type": "Image" },
+            { "type": "Widget" },
+            { "type": "Input.Number", "id": "qty", "value": "three" }
+        ]
+    });
+    let mut invocation = base_invocation(card.clone());
+    invocation.validation_mode = ValidationMode::Error;
+    let result = handle_invocation(invocation).expect("schema issues do not fail error mode");
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .filter(|issue| issue.code.starts_with("schema-"))
+            .all(|issue| issue.severity == Severity::Warning)
+    );
+    let schema_issues: Vec<(String, String)> = result
+        .validation_issues
+        .iter()
+        .filter(|issue| issue.code.starts_with("schema-"))
+        .map(|issue| (issue.code.clone(), issue.path.clone()))
+        .collect();
+    let expected = [
+        ("schema-invalid-value", "/body/0/weight"),
+        ("schema-missing-property", "/body/1"),
+        ("schema-unknown-type", "/body/2/type"),
+        ("schema-invalid-type", "/body/3/value"),
+    ];
+    for (code, path) in expected {
+        assert!(
+            schema_issues.iter().any(|(c, p)| c == code && p == path),
+            "expected {code} at {path}: {schema_issues:?}"
+        );
+    }
+    assert_eq!(schema_issues.len(), expected.len(), "{schema_issues:?}");
+
+    let mut newer = card;
+    newer["version"] = json!("1.2");
+    newer["body"] = json!([
+        { "type": "ActionSet", "actions": [ { "type": "Action.Submit", "title": "Go" } ] },
+        { "type": "Table", "fallback": { "type": "TextBlock", "text": "No table" } }
+    ]);
+    let result = handle_invocation(base_invocation(newer.clone())).expect("validation");
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .all(|issue| !issue.code.starts_with("schema-")),
+        "{:?}",
+        result.validation_issues
+    );
+
+    newer["version"] = json!("2.0");
+    let result = handle_invocation(base_invocation(newer)).expect("validation");
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .any(|issue| issue.code == "unsupported-version" && issue.path == "/version")
+    );
+}
+
+#[test]
+fn compatibility_warns_about_constructs_newer_than_version() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.2",
+        "body": [
+            { "type": "TextBlock", "text": "Report" },
+            { "type": "Table", "columns": [], "rows": [] }
+        ],
+        "actions": [
+            { "type": "Action.Execute", "verb": "refresh", "fallback": { "type": "Action.Submit" } },
+            {
+                "type": "Action.Submit",
+                "id": "send",
+                "data": { "type": "Table", "rtl": true, "metadata": { "webUrl": "https://example.com" } }
+            }
+        ]
+    });
+    let result = handle_invocation(base_invocation(card)).expect("validation");
+    assert_eq!(result.card_features.minimum_version.as_deref(), Some("1.5"));
+    let newer: Vec<_> = result
+        .validation_issues
+        .iter()
+        .filter(|issue| issue.code == "requires-newer-version")
+        .collect();
+    let warnings: Vec<&str> = newer.iter().map(|issue| issue.path.as_str()).collect();
+    assert_eq!(warnings, ["/body/1"]);
+    assert!(
+        newer
+            .iter()
+            .all(|issue| issue.severity == Severity::Warning)
+    );
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .all(|issue| issue.code != "schema-unknown-type"),
+        "a newer type is reported once: {:?}",
+        result.validation_issues
+    );
+}
+
+#[test]
+fn host_profile_reports_what_the_host_cannot_render() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.5",
+        "body": [
+            { "type": "TextBlock", "text": "Quarterly review" },
+            { "type": "Media", "sources": [ { "url": "https://example.com/q3.mp4" } ] },
+            { "type": "Table", "columns": [], "rows": [], "fallback": "drop" }
+        ],
+        "actions": [
+            { "type": "Action.Execute", "title": "Approve", "verb": "approve" },
+            { "type": "Action.Submit", "title": "Comment", "data": { "type": "Media" } }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.host_profile = Some(HostProfile::Webex);
+    let result = handle_invocation(invocation.clone()).expect("validation");
+    let host_issues: Vec<(&str, &str)> = result
+        .validation_issues
+        .iter()
+        .filter(|issue| issue.code.starts_with("host-"))
+        .map(|issue| (issue.code.as_str(), issue.path.as_str()))
+        .collect();
+    assert_eq!(
+        host_issues,
+        [
+            ("host-unsupported-version", "/version"),
+            ("host-unsupported-action", "/actions/0"),
+            ("host-unsupported-element", "/body/1"),
+        ]
+    );
+
+    invocation.host_profile = Some(HostProfile::Teams);
+    let result = handle_invocation(invocation).expect("validation");
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .all(|issue| !issue.code.starts_with("host-")),
+        "{:?}",
+        result.validation_issues
+    );
+}
+
+#[test]
+fn downgrade_rewrites_card_for_older_host() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.5",
+        "body": [
+            { "type": "TextBlock", "text": "Expense ${payload.id}" },
+            { "type": "Table", "columns": [], "rows": [], "fallback": { "type": "TextBlock", "text": "Open the report" } }
+        ],
+        "actions": [
+            { "type": "Action.Execute", "title": "Approve", "verb": "approve", "mode": "primary" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({ "id": 42 });
+    invocation.host_profile = Some(HostProfile::Webex);
+    invocation.downgrade = true;
+    let result = handle_invocation(invocation.clone()).expect("downgraded render");
+    let rendered = result.rendered_card.expect("card should render");
+    assert_eq!(rendered["version"], "1.3");
+    assert_eq!(rendered["body"][1]["text"], "Open the report");
+    assert_eq!(
+        rendered["actions"][0],
+        json!({ "type": "Action.Submit", "title": "Approve", "data": { "verb": "approve" } })
+    );
+    let codes: Vec<&str> = result
+        .transformations
+        .iter()
+        .map(|change| change.code.as_str())
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            "fallback-applied",
+            "action-rewritten",
+            "property-removed",
+            "version-lowered"
+        ]
+    );
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .all(|issue| !issue.code.starts_with("host-")
+                && issue.code != "requires-newer-version"),
+        "{:?}",
+        result.validation_issues
+    );
+
+    invocation.host_profile = None;
+    invocation.target_version = Some("2.0".into());
+    assert!(handle_invocation(invocation).is_err());
+}
+
+#[test]
+fn validation_checks_element_id_references() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "TextBlock", "id": "details", "text": "Details", "isVisible": false },
+            { "type": "TextBlock", "text": "Comment", "selectAction": {
+                "type": "Action.ToggleVisibility", "targetElements": ["comment"]
+            } },
+            { "type": "Image", "id": "details", "url": "https://example.com/a.png" }
+        ],
+        "actions": [
+            { "type": "Action.ToggleVisibility", "title": "More", "targetElements": ["details", "summary"] }
+        ]
+    });
+    let result = handle_invocation(base_invocation(card)).expect("validation");
+    let issues: Vec<(&str, &str)> = result
+        .validation_issues
+        .iter()
+        .filter(|issue| issue.code.contains("reference") || issue.code == "duplicate-element-id")
+        .map(|issue| (issue.code.as_str(), issue.path.as_str()))
+        .collect();
+    assert_eq!(
+        issues,
+        [
+            ("duplicate-element-id", "/body/2"),
+            (
+                "unknown-id-reference",
+                "/body/1/selectAction/targetElements/0"
+            ),
+            ("unknown-id-reference", "/actions/0/targetElements/1"),
+        ]
+    );
+}
+
+#[test]
+fn accessibility_rules_warn_with_configurable_levels() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [
+            { "type": "Image", "url": "https://example.com/logo.png" },
+            { "type": "Input.Text", "id": "email", "isRequired": true }
+        ],
+        "actions": [ { "type": "Action.Submit", "id": "send", "iconUrl": "https://example.com/send.png" } ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.validation_mode = ValidationMode::Error;
+    let result = handle_invocation(invocation.clone()).expect("warnings do not fail");
+    let issues: Vec<(&str, &str, Severity)> = result
+        .validation_issues
+        .iter()
+        .map(|issue| (issue.code.as_str(), issue.path.as_str(), issue.severity))
+        .collect();
+    assert_eq!(
+        issues,
+        [
+            ("a11y-icon-only-action", "/actions/0", Severity::Warning),
+            ("a11y-image-alt-text", "/body/0", Severity::Warning),
+            ("a11y-input-label", "/body/1", Severity::Warning),
+            ("a11y-required-error-message", "/body/1", Severity::Warning),
+        ]
+    );
+
+    invocation.rules = BTreeMap::from([
+        ("a11y-image-alt-text".to_string(), RuleLevel::Off),
+        ("a11y-input-label".to_string(), RuleLevel::Error),
+    ]);
+    match handle_invocation(invocation) {
+        Err(ComponentError::CardValidation(issues)) => {
+            let codes: Vec<(&str, Severity)> = issues
+                .iter()
+                .map(|issue| (issue.code.as_str(), issue.severity))
+                .collect();
+            assert_eq!(
+                codes,
+                [
+                    ("a11y-icon-only-action", Severity::Warning),
+                    ("a11y-input-label", Severity::Error),
+                    ("a11y-required-error-message", Severity::Warning),
+                ]
+            );
+        }
+        other => panic!("expected card validation failure, got {other:?}"),
+    }
+}
+
+#[test]
+fn error_mode_fails_only_on_error_severity() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.6",
+        "body": [ { "type": "Input.Toggle", "id": "agree", "label": "Agree" } ]
+    });
+    let missing_title = validation_rules()
+        .iter()
+        .find(|rule| rule.code == "missing-title")
+        .expect("catalogued rule");
+    assert_eq!(missing_title.severity, Severity::Warning);
+
+    let mut invocation = base_invocation(card);
+    invocation.validation_mode = ValidationMode::Error;
+    let result = handle_invocation(invocation.clone()).expect("warning does not fail");
+    assert_eq!(result.validation_issues.len(), 1);
+    assert_eq!(result.validation_issues[0].code, "missing-title");
+    assert_eq!(result.validation_issues[0].severity, Severity::Warning);
+
+    invocation.rules = BTreeMap::from([("missing-title".to_string(), RuleLevel::Off)]);
+    let result = handle_invocation(invocation.clone()).expect("rule disabled");
+    assert!(result.validation_issues.is_empty());
+
+    invocation.rules = BTreeMap::from([("missing-title".to_string(), RuleLevel::Error)]);
+    assert!(matches!(
+        handle_invocation(invocation),
+        Err(ComponentError::CardValidation(issues)) if issues[0].severity == Severity::Error
+    ));
+}
+
+#[test]
+fn fix_mode_returns_repaired_card_and_applied_fixes() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.0",
+        "body": [
+            { "type": "Input.Text", "id": "name", "label": "Name" },
+            { "type": "Input.Text", "id": "name", "label": "Nickname" }
+        ]
+    });
+    let result = handle_invocation(base_invocation(card.clone())).expect("validation");
+    let duplicate = result
+        .validation_issues
+        .iter()
+        .find(|issue| issue.code == "duplicate-id")
+        .expect("duplicate input id");
+    assert_eq!(
+        serde_json::to_value(&duplicate.fix).unwrap(),
+        json!([{ "op": "replace", "path": "/body/1/id", "value": "name_2" }])
+    );
+    assert!(result.applied_fixes.is_empty());
+
+    let mut invocation = base_invocation(card);
+    invocation.mode = InvocationMode::Fix;
+    let result = handle_invocation(invocation).expect("fix");
+    let applied: Vec<&str> = result
+        .applied_fixes
+        .iter()
+        .map(|issue| issue.code.as_str())
+        .collect();
+    assert_eq!(applied, ["duplicate-id", "requires-newer-version"]);
+    let rendered = result.rendered_card.expect("repaired card");
+    assert_eq!(rendered["version"], "1.3");
+    assert_eq!(rendered["body"][1]["id"], "name_2");
+    assert!(
+        result
+            .validation_issues
+            .iter()
+            .all(|issue| issue.fix.is_empty())
+    );
+}
+
+#[test]
+fn payload_size_is_reported_against_limits() {
+    let long_text = "x".repeat(600);
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.5",
+        "body": [
+            { "type": "TextBlock", "text": "Title", "wrap": false, "separator": false },
+            { "type": "TextBlock", "text": long_text, "wrap": true }
+        ]
+    });
+    let result = handle_invocation(base_invocation(card.clone())).expect("render");
+    let size = &result.payload_size;
+    assert_eq!(
+        size.bytes,
+        serde_json::to_vec(result.rendered_card.as_ref().unwrap())
+            .unwrap()
+            .len()
+    );
+    assert_eq!(size.limit, None);
+    assert_eq!(size.largest[0].path, "/body/1");
+    assert!(
+        !result
+            .validation_issues
+            .iter()
+            .any(|issue| issue.code.ends_with("payload-too-large"))
+    );
+
+    let mut invocation = base_invocation(card);
+    invocation.max_payload_bytes = Some(512);
+    invocation.minify = true;
+    invocation.host_profile = Some(HostProfile::Teams);
+    let result = handle_invocation(invocation).expect("render");
+    let size = &result.payload_size;
+    assert_eq!(size.limit, Some(512));
+    assert!(size.unminified_bytes.unwrap() > size.bytes);
+    assert_eq!(
+        result.rendered_card.unwrap()["body"][0],
+        json!({ "type": "TextBlock", "text": "Title" })
+    );
+    let issue = result
+        .validation_issues
+        .iter()
+        .find(|issue| issue.code == "payload-too-large")
+        .expect("configured limit exceeded");
+    assert!(issue.message.contains("/body/1"), "{}", issue.message);
+}
+
+#[test]
+fn url_safety_flags_schemes_hosts_and_untrusted_bindings() {
+    let input = json!({
+        "payload": {
+            "card_source": "inline",
+            "card_spec": {
+                "inline_json": {
+                    "type": "AdaptiveCard",
+                    "version": "1.5",
+                    "body": [
+                        { "type": "Image", "url": "https://cdn.example.com/logo.png", "altText": "Logo" }
+                    ],
+                    "actions": [
+                        { "type": "Action.OpenUrl", "title": "Open", "url": "${payload.link}" },
+                        { "type": "Action.OpenUrl", "title": "Run", "url": "javascript:alert(1)" }
+                    ]
+                }
+            },
+            "url_policy": { "allowed_hosts": ["example.com"] },
+            "payload": { "link": "https://phish.test/login" }
+        }
+    });
+    let output = component_adaptive_card::handle_message("card", &input.to_string());
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let issues: Vec<(&str, &str, &str)> = parsed["validationIssues"]
+        .as_array()
+        .unwrap_or_else(|| panic!("unexpected output: {parsed}"))
+        .iter()
+        .map(|issue| {
+            (
+                issue["code"].as_str().unwrap(),
+                issue["path"].as_str().unwrap(),
+                issue["severity"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        issues,
+        [
+            ("untrusted-url", "/actions/0/url", "warning"),
+            ("url-host-not-allowed", "/actions/0/url", "error"),
+            ("unsafe-url-scheme", "/actions/1/url", "error"),
+        ]
+    );
+}
+
+#[test]
+fn data_scopes_keep_the_provenance_of_their_source() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.5",
+        "body": [
+            { "type": "Image", "$data": "${state.images}", "url": "${url}", "altText": "Theme" },
+            { "type": "Image", "$data": "${payload.images}", "url": "${url}", "altText": "Upload" },
+            { "type": "Image", "url": "{{session.avatar}}", "altText": "Avatar" },
+            { "type": "TextBlock", "$data": "${state.profile}", "text": "${note}" },
+            { "type": "TextBlock", "$data": "${payload.profile}", "text": "${note}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.escape_policy = EscapePolicy::Markdown;
+    invocation.state = json!({
+        "images": [ { "url": "https://cdn.example.com/a.png" }, { "url": "https://cdn.example.com/b.png" } ],
+        "profile": { "note": "_kept_" }
+    });
+    invocation.payload = json!({
+        "images": [ { "url": "https://uploads.test/c.png" } ],
+        "profile": { "note": "_escaped_" }
+    });
+    invocation.session = json!({ "avatar": "https://avatars.test/me.png" });
+    let result = handle_invocation(invocation).expect("render");
+    let untrusted: Vec<&str> = result
+        .validation_issues
+        .iter()
+        .filter(|issue| issue.code == "untrusted-url")
+        .map(|issue| issue.path.as_str())
+        .collect();
+    assert_eq!(untrusted, ["/body/2/url", "/body/3/url"]);
+    let card = result.rendered_card.expect("card");
+    assert_eq!(card["body"][4]["text"], "_kept_");
+    assert_eq!(card["body"][5]["text"], "\\_escaped\\_");
+}
+
+#[test]
+fn untrusted_urls_are_tracked_through_handlebars_and_downgrade() {
+    let card = json!({
+        "type": "AdaptiveCard",
+        "version": "1.5",
+        "body": [
+            { "type": "Table", "columns": [], "rows": [], "fallback": "drop" },
+            { "type": "Image", "url": "{{payload.image}}", "altText": "Photo" },
+            { "type": "Image", "url": "https://cdn.example.com/{{state.theme}}.png", "altText": "Theme" }
+        ],
+        "actions": [
+            { "type": "Action.Execute", "title": "Run", "verb": "run", "fallback": "drop" },
+            { "type": "Action.OpenUrl", "title": "Open", "url": "${payload.link}" }
+        ]
+    });
+    let mut invocation = base_invocation(card);
+    invocation.payload = json!({
+        "image": "https://cdn.example.com/photo.png",
+        "link": "https://example.com/next"
+    });
+    invocation.state = json!({ "theme": "dark" });
+    invocation.downgrade = true;
+    invocation.target_version = Some("1.2".into());
+    let result = handle_invocation(invocation).expect("render");
+    let untrusted: Vec<&str> = result
+        .validation_issues
+        .iter()
+        .filter(|issue| issue.code == "untrusted-url")
+        .map(|issue| issue.path.as_str())
+        .collect();
+    assert_eq!(untrusted, ["/actions/0/url", "/body/0/url"]);
+    let card = result.rendered_card.expect("card");
+    assert!(!card.to_string().contains("untrusted"), "{card}");
+}